@@ -144,6 +144,16 @@ impl http_body::Body for Body {
     ) -> std::task::Poll<Result<Option<HeaderMap>, Self::Error>> {
         Pin::new(&mut self.0).poll_trailers(cx)
     }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.0.size_hint()
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.0.is_end_stream()
+    }
 }
 
 impl Stream for Body {
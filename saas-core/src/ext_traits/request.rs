@@ -1,5 +1,5 @@
 use crate::body::Body;
-use crate::extract::{DefaultBodyLimitKind, FromRequest, FromRequestParts, Request};
+use crate::extract::{DefaultBodyLimit, FromRequest, FromRequestParts, Request};
 use futures_util::future::BoxFuture;
 use http_body::Limited;
 
@@ -87,15 +87,9 @@ impl RequestExt for Request {
     }
 
     fn with_limited_body(self) -> Result<Request<Limited<Body>>, Request> {
-        const DEFAULT_LIMIT: usize = 2_097_152;
-
-        match self.extensions().get::<DefaultBodyLimitKind>().copied() {
-            Some(DefaultBodyLimitKind::Disable) => Err(self),
-            Some(DefaultBodyLimitKind::Limit(limit)) => {
-                Ok(self.map(|b| http_body::Limited::new(b, DEFAULT_LIMIT)))
-            }
-            None => Ok(self.map(|b| http_body::Limited::new(b, DEFAULT_LIMIT))),
-
+        match DefaultBodyLimit::__resolve(self.extensions(), self.headers()) {
+            Some(limit) => Ok(self.map(|b| http_body::Limited::new(b, limit))),
+            None => Err(self),
         }
     }
 
@@ -1,6 +1,10 @@
+use http::{header::CONTENT_TYPE, Extensions, HeaderMap};
 use private::DefaultBodyLimitService;
+use std::sync::Arc;
 use tower_layer::Layer;
 
+/// The limit used when no `DefaultBodyLimit` has been set.
+const DEFAULT_LIMIT: usize = 2_097_152;
 
 /// let app = Router::new()
 ///     .route(
@@ -10,29 +14,153 @@ use tower_layer::Layer;
 ///         post(|request: Request| async{}),
 ///     )
 ///     .lay(RequestBodyLimitLayer::new(1024));
-/// 
+///
+/// Limits can also depend on the `Content-Type` of the request:
+///
+/// ```rust,no_run
+/// use saas::{extract::DefaultBodyLimit, Router};
+///
+/// let app = Router::new()
+///     // routes...
+///     .layer(
+///         DefaultBodyLimit::max(1024 * 1024)
+///             .content_type("multipart/form-data", 500 * 1024 * 1024)
+///             .content_type("image/*", 20 * 1024 * 1024),
+///     );
+/// # let _: Router = app;
+/// ```
+///
+/// Exceeding the limit is rejected with `413 Payload Too Large` and a body that includes the
+/// limit, see [`BodyLimitExceeded`](super::rejection::BodyLimitExceeded).
 #[derive(Debug, Clone)]
 #[must_use]
 pub struct DefaultBodyLimit {
     kind: DefaultBodyLimitKind,
+    content_types: Arc<Vec<(String, DefaultBodyLimitKind)>>,
 }
 
 #[derive(Debug, Clone, Copy)]
-pub(crate) enum DefaultBodyLimitKind {
+enum DefaultBodyLimitKind {
     Disable,
     Limit(usize),
 }
 
+impl DefaultBodyLimitKind {
+    fn limit(self) -> Option<usize> {
+        match self {
+            Self::Disable => None,
+            Self::Limit(limit) => Some(limit),
+        }
+    }
+}
+
+// Limit set for a single route, takes precedence over `DefaultBodyLimit` layers.
+#[derive(Debug, Clone)]
+struct RouteBodyLimit(DefaultBodyLimit);
+
 impl DefaultBodyLimit {
+    /// Disable the default request body limit.
     pub fn disable() -> Self {
-        Self {
-            kind: DefaultBodyLimitKind::Disable,
-        }
+        Self::new(DefaultBodyLimitKind::Disable)
     }
 
+    /// Set the default request body limit to `limit` bytes.
     pub fn max(limit: usize) -> Self {
+        Self::new(DefaultBodyLimitKind::Limit(limit))
+    }
+
+    fn new(kind: DefaultBodyLimitKind) -> Self {
         Self {
-            kind: DefaultBodyLimitKind::Limit(limit),
+            kind,
+            content_types: Arc::new(Vec::new()),
+        }
+    }
+
+    /// Use a different limit for requests with the given `Content-Type`.
+    ///
+    /// `content_type` is either a full media type such as `application/json`, or a type with a
+    /// wildcard subtype such as `image/*`. Parameters of the request's `Content-Type` are
+    /// ignored, and a full media type is preferred over a wildcard.
+    pub fn content_type(self, content_type: &str, limit: usize) -> Self {
+        self.with_content_type(content_type, DefaultBodyLimitKind::Limit(limit))
+    }
+
+    /// Disable the limit for requests with the given `Content-Type`.
+    ///
+    /// See [`DefaultBodyLimit::content_type`] for how `content_type` is matched.
+    pub fn disable_content_type(self, content_type: &str) -> Self {
+        self.with_content_type(content_type, DefaultBodyLimitKind::Disable)
+    }
+
+    fn with_content_type(mut self, content_type: &str, kind: DefaultBodyLimitKind) -> Self {
+        let content_type = content_type.trim().to_ascii_lowercase();
+        let content_types = Arc::make_mut(&mut self.content_types);
+        content_types.retain(|(existing, _)| *existing != content_type);
+        content_types.push((content_type, kind));
+        self
+    }
+
+    /// The limit for requests without a more specific `Content-Type` limit, or `None` if it is
+    /// disabled.
+    pub fn limit(&self) -> Option<usize> {
+        self.kind.limit()
+    }
+
+    /// The limit that applies to a request with the given headers, or `None` if it is
+    /// disabled.
+    pub fn limit_for(&self, headers: &HeaderMap) -> Option<usize> {
+        self.kind_for(headers).limit()
+    }
+
+    fn kind_for(&self, headers: &HeaderMap) -> DefaultBodyLimitKind {
+        let mime = match headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<mime::Mime>().ok())
+        {
+            Some(mime) => mime,
+            None => return self.kind,
+        };
+
+        let essence = mime.essence_str();
+        let mut wildcard = None;
+        for (content_type, kind) in self.content_types.iter() {
+            if content_type == essence {
+                return *kind;
+            }
+            if content_type
+                .strip_suffix("/*")
+                .is_some_and(|type_| type_ == mime.type_().as_str())
+            {
+                wildcard = Some(*kind);
+            }
+        }
+        wildcard.unwrap_or(self.kind)
+    }
+
+    // Used by extractors in `saas` that rebuild requests from an already limited body.
+    #[doc(hidden)]
+    pub fn __insert_into(self, extensions: &mut http::Extensions) {
+        extensions.remove::<RouteBodyLimit>();
+        extensions.insert(self);
+    }
+
+    // Used by `MethodRouter` for limits set on a route.
+    #[doc(hidden)]
+    pub fn __insert_route_limit_into(self, extensions: &mut http::Extensions) {
+        extensions.insert(RouteBodyLimit(self));
+    }
+
+    // The limit that applies to a request, used by the extractors that read the body.
+    #[doc(hidden)]
+    pub fn __resolve(extensions: &Extensions, headers: &HeaderMap) -> Option<usize> {
+        let limit = extensions
+            .get::<RouteBodyLimit>()
+            .map(|route| &route.0)
+            .or_else(|| extensions.get::<DefaultBodyLimit>());
+        match limit {
+            Some(limit) => limit.limit_for(headers),
+            None => Some(DEFAULT_LIMIT),
         }
     }
 }
@@ -43,20 +171,21 @@ impl<S> Layer<S> for DefaultBodyLimit {
     fn layer(&self, inner: S) -> Self::Service {
         DefaultBodyLimitService {
             inner,
-            kind: self.kind,
+            limit: self.clone(),
         }
     }
 }
 
 mod private {
-    use super::DefaultBodyLimitKind;
+    use super::DefaultBodyLimit;
     use http::Request;
     use std::task::Context;
     use tower_service::Service;
 
+    #[derive(Debug, Clone)]
     pub struct DefaultBodyLimitService<S> {
         pub(super) inner: S,
-        pub(super) kind: DefaultBodyLimitKind,
+        pub(super) limit: DefaultBodyLimit,
     }
 
     impl<B, S> Service<Request<B>> for DefaultBodyLimitService<S>
@@ -74,10 +203,53 @@ mod private {
 
         #[inline]
         fn call(&mut self, mut req: Request<B>) -> Self::Future {
-            req.extensions_mut().insert(self.kind);
+            req.extensions_mut().insert(self.limit.clone());
             self.inner.call(req)
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn headers(content_type: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        headers
+    }
 
+    #[test]
+    fn content_type_limits() {
+        let limit = DefaultBodyLimit::max(10)
+            .content_type("image/*", 20)
+            .content_type("image/png", 30)
+            .disable_content_type("multipart/form-data");
 
+        assert_eq!(limit.limit_for(&HeaderMap::new()), Some(10));
+        assert_eq!(limit.limit_for(&headers("application/json")), Some(10));
+        assert_eq!(limit.limit_for(&headers("image/jpeg")), Some(20));
+        assert_eq!(limit.limit_for(&headers("IMAGE/PNG")), Some(30));
+        assert_eq!(
+            limit.limit_for(&headers("multipart/form-data; boundary=abc")),
+            None
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn route_limit_takes_precedence() {
+        let mut extensions = Extensions::new();
+        assert_eq!(
+            DefaultBodyLimit::__resolve(&extensions, &HeaderMap::new()),
+            Some(DEFAULT_LIMIT)
+        );
+
+        DefaultBodyLimit::max(10).__insert_route_limit_into(&mut extensions);
+        extensions.insert(DefaultBodyLimit::max(20));
+        assert_eq!(
+            DefaultBodyLimit::__resolve(&extensions, &HeaderMap::new()),
+            Some(10)
+        );
+    }
+}
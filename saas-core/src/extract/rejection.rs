@@ -2,6 +2,143 @@ use crate::__composite_rejection as composite_rejection;
 use crate::__define_rejection as define_rejection;
 
 use crate::{BoxError, Error};
+use http::StatusCode;
+use std::borrow::Cow;
+
+/// Response extension holding the rejection a response was created from.
+///
+/// Added by every built-in rejection. Middleware use it to recognize rejections and replace
+/// their responses, such as the rejection hook on `Router`, problem details and translated
+/// messages.
+///
+/// Composite rejections such as `JsonRejection` replace the value with themselves, so it can be
+/// downcast to the outermost rejection type, for example `JsonRejection` rather than
+/// `MissingJsonContentType`. The concrete rejection is identified by [`name`](Self::name).
+#[derive(Debug)]
+pub struct AnyRejection {
+    status: StatusCode,
+    name: &'static str,
+    title: Cow<'static, str>,
+    detail: Option<String>,
+    body_text: String,
+    rejection: BoxError,
+}
+
+impl AnyRejection {
+    #[doc(hidden)]
+    pub fn __new<T>(status: StatusCode, body_text: String, rejection: T) -> Self
+    where
+        T: std::error::Error + Send + Sync + 'static,
+    {
+        let name = std::any::type_name::<T>();
+        Self {
+            status,
+            name: name.rsplit("::").next().unwrap_or(name),
+            title: Cow::Owned(body_text.clone()),
+            detail: None,
+            body_text,
+            rejection: Box::new(rejection),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn __with_message(
+        self,
+        name: &'static str,
+        title: &'static str,
+        detail: Option<String>,
+    ) -> Self {
+        Self {
+            name,
+            title: Cow::Borrowed(title),
+            detail,
+            ..self
+        }
+    }
+
+    /// Wrap the rejection in the composite rejection `C` if it is a `T`.
+    #[doc(hidden)]
+    pub fn __wrap<T, C>(self, f: fn(T) -> C) -> Self
+    where
+        T: std::error::Error + Send + Sync + 'static,
+        C: std::error::Error + Send + Sync + 'static,
+    {
+        match self.rejection.downcast::<T>() {
+            Ok(inner) => Self {
+                rejection: Box::new(f(*inner)),
+                ..self
+            },
+            Err(rejection) => Self { rejection, ..self },
+        }
+    }
+
+    /// The status code of the rejection.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// A name identifying the rejection, such as `MissingJsonContentType`.
+    ///
+    /// This is the name of the concrete rejection type, or of the kind of rejection for types
+    /// that cover several, such as `MissingAuthorizationHeader`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The fixed part of the response body, such as
+    /// ``Expected request with `Content-Type: application/json` ``.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The variable part of the response body, such as the error that caused the rejection.
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    /// The response body text of the rejection.
+    pub fn body_text(&self) -> &str {
+        &self.body_text
+    }
+
+    /// The error that caused the rejection, if any.
+    pub fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.rejection.source()
+    }
+
+    /// Returns `true` if the rejection is a `T`.
+    pub fn is<T>(&self) -> bool
+    where
+        T: std::error::Error + 'static,
+    {
+        self.rejection.is::<T>()
+    }
+
+    /// Get a reference to the rejection if it is a `T`.
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: std::error::Error + 'static,
+    {
+        self.rejection.downcast_ref()
+    }
+
+    /// Take the rejection if it is a `T`, or get `self` back.
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: std::error::Error + 'static,
+    {
+        match self.rejection.downcast::<T>() {
+            Ok(rejection) => Ok(*rejection),
+            Err(rejection) => Err(Self { rejection, ..self }),
+        }
+    }
+}
+
+impl std::fmt::Display for AnyRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.body_text)
+    }
+}
 
 composite_rejection! {
     pub enum FailedToBufferBody {
@@ -11,7 +148,7 @@ composite_rejection! {
 }
 
 impl FailedToBufferBody {
-    pub(crate) fn from_err<E>(err: E) -> Self
+    pub(crate) fn from_err<E>(err: E, limit: Option<usize>) -> Self
     where
         E: Into<BoxError>,
     {
@@ -20,7 +157,10 @@ impl FailedToBufferBody {
             Err(err) => err,
         };
         match box_error.downcast::<http_body::LengthLimitError>() {
-            Ok(err) => Self::LengthLimitError(LengthLimitError::from_err(err)),
+            Ok(err) => Self::LengthLimitError(match limit {
+                Some(limit) => LengthLimitError::from_err(BodyLimitExceeded::__new(limit)),
+                None => LengthLimitError::from_err(err),
+            }),
             Err(err) => Self::UnknowBodyError(UnknowBodyError::from_err(err)),
         }
     }
@@ -28,10 +168,39 @@ impl FailedToBufferBody {
 
 define_rejection! {
     #[status = PAYLOAD_TOO_LARGE]
-    #[body = "Failed to buffer the request body"]
+    #[body = "Request body is too large"]
     pub struct LengthLimitError(Error);
 }
 
+/// Error for a request body that is larger than the limit set with `DefaultBodyLimit`.
+///
+/// The source of the rejections for a body that is too large, so the limit can be recovered
+/// from them.
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimitExceeded {
+    limit: usize,
+}
+
+impl BodyLimitExceeded {
+    #[doc(hidden)]
+    pub fn __new(limit: usize) -> Self {
+        Self { limit }
+    }
+
+    /// The limit in bytes.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl std::fmt::Display for BodyLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "length limit of {} bytes exceeded", self.limit)
+    }
+}
+
+impl std::error::Error for BodyLimitExceeded {}
+
 define_rejection! {
     #[status = BAD_REQUEST]
     #[body = "Failed to buffer the request body"]
@@ -1,4 +1,4 @@
-use super::{FromRequest, FromRequestParts ,Request, rejection::{BytesRejection, FailedToBufferBody, StringRejection, InvalidUtf8}};
+use super::{DefaultBodyLimit, FromRequest, FromRequestParts ,Request, rejection::{BytesRejection, FailedToBufferBody, StringRejection, InvalidUtf8}};
 use crate::{body::Body, ext_traits::request::RequestExt};
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -70,13 +70,14 @@ where
 {
     type Rejection = BytesRejection;
     async fn from_request(req: Request, _: &S) -> Result<Self, Self::Rejection> {
+        let limit = DefaultBodyLimit::__resolve(req.extensions(), req.headers());
         let bytes = match req.into_limited_body() {
             Ok(limited_body) => crate::body::to_bytes(limited_body)
                 .await
-                .map_err(FailedToBufferBody::from_err)?,
+                .map_err(|err| FailedToBufferBody::from_err(err, limit))?,
             Err(unlimited_body) => crate::body::to_bytes(unlimited_body)
                 .await
-                .map_err(FailedToBufferBody::from_err)?,
+                .map_err(|err| FailedToBufferBody::from_err(err, limit))?,
         };
 
         Ok(bytes)
@@ -127,4 +128,22 @@ where
     async fn from_request(req: Request, _: &S) -> Result<Self, Self::Rejection> {
         Ok(req.into_body())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn length_limit_includes_limit() {
+        let mut req = Request::new(Body::from(vec![0; 16]));
+        DefaultBodyLimit::max(8).__insert_into(req.extensions_mut());
+
+        let rejection = Bytes::from_request(req, &()).await.unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            rejection.body_text(),
+            "Request body is too large: length limit of 8 bytes exceeded"
+        );
+    }
+}
@@ -43,7 +43,16 @@ macro_rules! __define_rejection {
                     body_text = $body,
                     status = http::StatusCode::$status,
                 );
-                (self.status(), $body).into_response()
+                let mut res = (self.status(), $body).into_response();
+                res.extensions_mut().insert(
+                    $crate::extract::rejection::AnyRejection::__new(
+                        http::StatusCode::$status,
+                        $body.into(),
+                        self,
+                    )
+                    .__with_message(stringify!($name), $body, None),
+                );
+                res
             }
         }
 
@@ -100,7 +109,18 @@ macro_rules! __define_rejection {
                     body_text = self.body_text(),
                     status = http::StatusCode::$status,
                 );
-                (self.status(), self.body_text()).into_response()
+                let body_text = self.body_text();
+                let detail = self.0.to_string();
+                let mut res = (self.status(), body_text.clone()).into_response();
+                res.extensions_mut().insert(
+                    $crate::extract::rejection::AnyRejection::__new(
+                        http::StatusCode::$status,
+                        body_text,
+                        self,
+                    )
+                    .__with_message(stringify!($name), $body, Some(detail)),
+                );
+                res
             }
         }
 
@@ -153,11 +173,19 @@ macro_rules! __composite_rejection {
 
         impl $crate::response::IntoResponse for $name {
             fn into_response(self) -> $crate::response::Response {
-                match self {
+                let mut res = match self {
                     $(
                         Self::$variant(inner) => inner.into_response(),
                     )+
+                };
+                if let Some(rejection) = res
+                    .extensions_mut()
+                    .remove::<$crate::extract::rejection::AnyRejection>()
+                {
+                    let rejection = rejection$(.__wrap(Self::$variant))+;
+                    res.extensions_mut().insert(rejection);
                 }
+                res
             }
         }
 
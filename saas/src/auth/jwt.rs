@@ -0,0 +1,474 @@
+//! Extractor for JSON Web Tokens sent as bearer tokens.
+//!
+//! [`Claims`] validates the token with the [`JwtKeys`] in the state and deserializes its claims:
+//!
+//! ```rust,no_run
+//! use saas::{
+//!     auth::jwt::{Claims, JwtKey, JwtKeys},
+//!     routing::get,
+//!     Router,
+//! };
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     sub: String,
+//!     admin: bool,
+//! }
+//!
+//! async fn me(Claims(user): Claims<User>) -> String {
+//!     user.sub
+//! }
+//!
+//! let keys = JwtKeys::new()
+//!     .issuer("https://auth.example.com")
+//!     .audience("api")
+//!     .with_key(JwtKey::hs256(b"current secret").with_kid("2023-10"))
+//!     .with_key(JwtKey::hs256(b"previous secret").with_kid("2023-09"));
+//!
+//! let app = Router::new().route("/me", get(me)).with_state(keys);
+//! # let _: Router = app;
+//! ```
+//!
+//! # Key rotation
+//!
+//! A token with a `kid` header is only checked against the key with that id, otherwise every
+//! key with the algorithm of the token is tried. [`JwtKeys`] is cheap to clone and all clones
+//! share the same keys, so keys can be rotated without rebuilding the router with
+//! [`JwtKeys::insert`] and [`JwtKeys::remove`].
+//!
+//! # Rejections
+//!
+//! Requests are rejected with `401 Unauthorized` and a `WWW-Authenticate` header as described
+//! in [RFC 6750]: `Bearer` if there is no token, `Bearer error="invalid_request"` if the
+//! `Authorization` header is malformed and `Bearer error="invalid_token"` if the token is
+//! invalid, for example because it has expired or the signature doesn't match.
+//!
+//! [RFC 6750]: https://www.rfc-editor.org/rfc/rfc6750#section-3
+
+use super::{Authorization, Bearer};
+use crate::extract::{rejection::AnyRejection, FromRef, FromRequestParts};
+use async_trait::async_trait;
+use http::{header::WWW_AUTHENTICATE, request::Parts, HeaderValue, StatusCode};
+use jsonwebtoken::{decode, decode_header, errors::ErrorKind, Algorithm, DecodingKey, Validation};
+use saas_core::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use std::{
+    error::Error,
+    fmt,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+/// Extractor for the validated claims of a JSON Web Token.
+///
+/// The token is taken from the `Authorization: Bearer` header and validated with the
+/// [`JwtKeys`] extracted from the state. The signature, `exp` and `nbf` are always validated,
+/// `iss` and `aud` if [`JwtKeys::issuer`] and [`JwtKeys::audience`] have been set.
+///
+/// See the [module docs](self) for an example.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Claims<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Claims<T>
+where
+    T: DeserializeOwned,
+    JwtKeys: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = JwtRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Authorization(bearer) = Authorization::<Bearer>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| {
+                if rejection.is_missing() {
+                    JwtRejection::new(RejectionKind::Missing)
+                } else {
+                    JwtRejection::new(RejectionKind::Malformed)
+                }
+            })?;
+
+        JwtKeys::from_ref(state).decode(bearer.token()).map(Self)
+    }
+}
+
+saas_core::__impl_deref!(Claims);
+
+/// The keys and validation rules used by [`Claims`].
+///
+/// Add it to the state of the router. Clones share the same set of keys.
+#[derive(Clone, Default)]
+pub struct JwtKeys {
+    keys: Arc<RwLock<Vec<JwtKey>>>,
+    issuer: Option<Vec<String>>,
+    audience: Option<Vec<String>>,
+    leeway: u64,
+}
+
+impl JwtKeys {
+    /// Create an empty key set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key.
+    ///
+    /// A key with the same `kid` is replaced.
+    pub fn with_key(self, key: JwtKey) -> Self {
+        self.insert(key);
+        self
+    }
+
+    /// Require `iss` to be `issuer`.
+    ///
+    /// Can be called multiple times to allow several issuers.
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer.get_or_insert_with(Vec::new).push(issuer.into());
+        self
+    }
+
+    /// Require `aud` to contain `audience`.
+    ///
+    /// Can be called multiple times to allow several audiences. If not set, tokens with an
+    /// `aud` claim are accepted regardless of its value.
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience
+            .get_or_insert_with(Vec::new)
+            .push(audience.into());
+        self
+    }
+
+    /// Set the number of seconds of clock skew allowed when validating `exp` and `nbf`.
+    ///
+    /// Defaults to 0.
+    pub fn leeway(mut self, seconds: u64) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// Add a key to this set and all its clones.
+    ///
+    /// A key with the same `kid` is replaced.
+    pub fn insert(&self, key: JwtKey) {
+        let mut keys = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+        keys.retain(|existing| key.kid.is_none() || existing.kid != key.kid);
+        keys.push(key);
+    }
+
+    /// Remove the key with the given `kid` from this set and all its clones.
+    ///
+    /// Returns `true` if a key was removed.
+    pub fn remove(&self, kid: &str) -> bool {
+        let mut keys = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+        let len = keys.len();
+        keys.retain(|key| key.kid.as_deref() != Some(kid));
+        keys.len() != len
+    }
+
+    /// Validate `token` and deserialize its claims.
+    ///
+    /// This is what [`Claims`] uses and is useful for tokens sent some other way, for example
+    /// in a query parameter of a WebSocket upgrade.
+    pub fn decode<T>(&self, token: &str) -> Result<T, JwtRejection>
+    where
+        T: DeserializeOwned,
+    {
+        let header = decode_header(token).map_err(JwtRejection::invalid_token)?;
+        let keys = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+
+        let mut last_error = None;
+        for key in keys.iter().filter(|key| {
+            key.algorithm == header.alg && (header.kid.is_none() || key.kid == header.kid)
+        }) {
+            match decode::<T>(token, &key.key, &self.validation(key.algorithm)) {
+                Ok(data) => return Ok(data.claims),
+                // try the next key
+                Err(err) if matches!(err.kind(), ErrorKind::InvalidSignature) => {
+                    last_error = Some(err)
+                }
+                Err(err) => return Err(JwtRejection::invalid_token(err)),
+            }
+        }
+
+        Err(match last_error {
+            Some(err) => JwtRejection::invalid_token(err),
+            None => JwtRejection::new(RejectionKind::UnknownKey),
+        })
+    }
+
+    fn validation(&self, algorithm: Algorithm) -> Validation {
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = self.leeway;
+        validation.validate_nbf = true;
+        match &self.audience {
+            Some(audience) => validation.set_audience(audience),
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(issuer);
+        }
+        validation
+    }
+}
+
+impl fmt::Debug for JwtKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKeys")
+            .field(
+                "keys",
+                &*self.keys.read().unwrap_or_else(PoisonError::into_inner),
+            )
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .field("leeway", &self.leeway)
+            .finish()
+    }
+}
+
+/// A key used to verify the signature of tokens.
+#[derive(Clone)]
+pub struct JwtKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+impl JwtKey {
+    /// A shared secret for `HS256`.
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self {
+            kid: None,
+            algorithm: Algorithm::HS256,
+            key: DecodingKey::from_secret(secret),
+        }
+    }
+
+    /// A PEM encoded RSA public key for `RS256`.
+    pub fn rs256_pem(pem: &[u8]) -> Result<Self, InvalidJwtKey> {
+        Ok(Self {
+            kid: None,
+            algorithm: Algorithm::RS256,
+            key: DecodingKey::from_rsa_pem(pem).map_err(InvalidJwtKey)?,
+        })
+    }
+
+    /// A PEM encoded elliptic curve public key for `ES256`.
+    pub fn es256_pem(pem: &[u8]) -> Result<Self, InvalidJwtKey> {
+        Ok(Self {
+            kid: None,
+            algorithm: Algorithm::ES256,
+            key: DecodingKey::from_ec_pem(pem).map_err(InvalidJwtKey)?,
+        })
+    }
+
+    /// Set the key id, matched against the `kid` header of tokens.
+    pub fn with_kid(mut self, kid: impl Into<String>) -> Self {
+        self.kid = Some(kid.into());
+        self
+    }
+}
+
+impl fmt::Debug for JwtKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKey")
+            .field("kid", &self.kid)
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned when a [`JwtKey`] can't be parsed.
+#[derive(Debug)]
+pub struct InvalidJwtKey(jsonwebtoken::errors::Error);
+
+impl fmt::Display for InvalidJwtKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid JWT key: {}", self.0)
+    }
+}
+
+impl Error for InvalidJwtKey {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Rejection used for [`Claims`].
+///
+/// Always responds with `401 Unauthorized`. See the [module docs](self#rejections) for the
+/// `WWW-Authenticate` header.
+#[derive(Debug)]
+pub struct JwtRejection {
+    kind: RejectionKind,
+}
+
+#[derive(Debug)]
+enum RejectionKind {
+    Missing,
+    Malformed,
+    InvalidToken(jsonwebtoken::errors::Error),
+    UnknownKey,
+}
+
+impl JwtRejection {
+    fn new(kind: RejectionKind) -> Self {
+        Self { kind }
+    }
+
+    fn invalid_token(err: jsonwebtoken::errors::Error) -> Self {
+        Self::new(RejectionKind::InvalidToken(err))
+    }
+
+    /// Returns `true` if the token has expired.
+    pub fn is_expired(&self) -> bool {
+        matches!(
+            &self.kind,
+            RejectionKind::InvalidToken(err) if matches!(err.kind(), ErrorKind::ExpiredSignature)
+        )
+    }
+
+    /// Get the response body text used for this rejection.
+    pub fn body_text(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get the status code used for this rejection.
+    pub fn status(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+
+    fn challenge(&self) -> HeaderValue {
+        HeaderValue::from_static(match self.kind {
+            RejectionKind::Missing => "Bearer",
+            RejectionKind::Malformed => "Bearer error=\"invalid_request\"",
+            RejectionKind::InvalidToken(_) | RejectionKind::UnknownKey => {
+                "Bearer error=\"invalid_token\""
+            }
+        })
+    }
+}
+
+impl fmt::Display for JwtRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            RejectionKind::Missing => f.write_str("Missing `Authorization` header"),
+            RejectionKind::Malformed => f.write_str("Malformed `Authorization` header"),
+            RejectionKind::InvalidToken(err) => write!(f, "Invalid token: {}", err),
+            RejectionKind::UnknownKey => f.write_str("Invalid token: unknown key"),
+        }
+    }
+}
+
+impl Error for JwtRejection {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            RejectionKind::InvalidToken(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for JwtRejection {
+    fn into_response(self) -> Response {
+        saas_core::__log_rejection!(
+            rejection_type = JwtRejection,
+            body_text = self.body_text(),
+            status = self.status(),
+        );
+        let status = self.status();
+        let body_text = self.body_text();
+        let (name, detail) = match &self.kind {
+            RejectionKind::Missing => ("MissingAuthorizationHeader", None),
+            RejectionKind::Malformed => ("MalformedAuthorizationHeader", None),
+            RejectionKind::InvalidToken(err) => ("InvalidToken", Some(err.to_string())),
+            RejectionKind::UnknownKey => ("UnknownKey", None),
+        };
+        let mut res = (
+            status,
+            [(WWW_AUTHENTICATE, self.challenge())],
+            body_text.clone(),
+        )
+            .into_response();
+        res.extensions_mut().insert(
+            AnyRejection::__new(status, body_text.clone(), self).__with_message(
+                name,
+                "Unauthorized",
+                Some(detail.unwrap_or(body_text)),
+            ),
+        );
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde::{Deserialize, Serialize};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct User {
+        sub: String,
+        exp: u64,
+    }
+
+    fn token(kid: Option<&str>, secret: &[u8], exp_offset: i64) -> (String, u64) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let exp = (now as i64 + exp_offset) as u64;
+        let header = Header {
+            kid: kid.map(Into::into),
+            ..Header::default()
+        };
+        let claims = User {
+            sub: "alice".to_owned(),
+            exp,
+        };
+        let token = encode(&header, &claims, &EncodingKey::from_secret(secret)).unwrap();
+        (token, exp)
+    }
+
+    #[test]
+    fn selects_key_by_kid() {
+        let keys = JwtKeys::new()
+            .with_key(JwtKey::hs256(b"new").with_kid("new"))
+            .with_key(JwtKey::hs256(b"old").with_kid("old"));
+
+        let (old, exp) = token(Some("old"), b"old", 60);
+        assert_eq!(
+            keys.decode::<User>(&old).unwrap(),
+            User {
+                sub: "alice".to_owned(),
+                exp
+            }
+        );
+
+        // tokens without a `kid` are checked against every key
+        let (no_kid, _) = token(None, b"new", 60);
+        assert!(keys.decode::<User>(&no_kid).is_ok());
+
+        let (wrong_kid, _) = token(Some("new"), b"old", 60);
+        assert!(keys.decode::<User>(&wrong_kid).is_err());
+
+        assert!(keys.clone().remove("old"));
+        let rejection = keys.decode::<User>(&old).unwrap_err();
+        assert_eq!(rejection.to_string(), "Invalid token: unknown key");
+    }
+
+    #[test]
+    fn rejects_expired_tokens() {
+        let keys = JwtKeys::new().with_key(JwtKey::hs256(b"secret"));
+        let (expired, _) = token(None, b"secret", -60);
+
+        let rejection = keys.decode::<User>(&expired).unwrap_err();
+        assert!(rejection.is_expired());
+
+        let res = rejection.into_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers()[WWW_AUTHENTICATE],
+            "Bearer error=\"invalid_token\""
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! Extractors and middleware for the `Authorization` header.
+//!
+//! [`Authorization`] parses the credentials of the request. The scheme is picked with the type
+//! parameter, either [`Bearer`] or [`Basic`]:
+//!
+//! ```rust,no_run
+//! use saas::{
+//!     auth::{Authorization, Basic, Bearer},
+//!     routing::get,
+//!     Router,
+//! };
+//!
+//! async fn api(Authorization(bearer): Authorization<Bearer>) {
+//!     let token: &str = bearer.token();
+//!     // ...
+//! }
+//!
+//! async fn admin(Authorization(basic): Authorization<Basic>) {
+//!     let (username, password) = (basic.username(), basic.password());
+//!     // ...
+//! }
+//!
+//! let app = Router::new()
+//!     .route("/api", get(api))
+//!     .route("/admin", get(admin));
+//! # let _: Router = app;
+//! ```
+//!
+//! Requests without credentials, or with credentials that can't be parsed, are rejected with
+//! `401 Unauthorized` and a `WWW-Authenticate` header with the challenge of the scheme.
+//!
+//! [`RequireAuth`] additionally checks the credentials with a [`VerifyCredentials`] held in the
+//! state and is meant to be used with
+//! [`middleware::from_extractor_with_state`](crate::middleware::from_extractor_with_state).
+//!
+//! With the `jwt` feature, [`jwt::Claims`] validates bearer tokens that are JSON Web Tokens.
+
+#[cfg(feature = "jwt")]
+pub mod jwt;
+
+use crate::extract::{rejection::AnyRejection, FromRef, FromRequestParts};
+use async_trait::async_trait;
+use base64::engine::{general_purpose::STANDARD, Engine as _};
+use http::{
+    header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    request::Parts,
+    HeaderValue, StatusCode,
+};
+use saas_core::response::{IntoResponse, Response};
+use std::{fmt, marker::PhantomData};
+
+/// Credentials of an authentication scheme.
+///
+/// Implemented by [`Bearer`] and [`Basic`]. Implement it for other schemes to use them with
+/// [`Authorization`] and [`RequireAuth`].
+pub trait Credentials: Sized + Send + Sync + 'static {
+    /// The name of the scheme, such as `Bearer`. Matched case insensitively.
+    const SCHEME: &'static str;
+
+    /// Decode the credentials from the part of the header value after the scheme.
+    ///
+    /// Returns `None` if the credentials are malformed.
+    fn decode(value: &str) -> Option<Self>;
+
+    /// The value of the `WWW-Authenticate` header sent if the credentials are missing,
+    /// malformed or rejected.
+    fn challenge() -> HeaderValue;
+}
+
+/// Credentials of the `Bearer` scheme, as used for OAuth 2.0 access tokens.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Bearer {
+    token: String,
+}
+
+impl Bearer {
+    /// Create credentials from a token.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+        }
+    }
+
+    /// The token.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl Credentials for Bearer {
+    const SCHEME: &'static str = "Bearer";
+
+    fn decode(value: &str) -> Option<Self> {
+        // token68 from RFC 7235
+        let is_token68 = !value.is_empty()
+            && value.trim_end_matches('=').bytes().all(|byte| {
+                byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'+' | b'/')
+            });
+
+        is_token68.then(|| Self::new(value))
+    }
+
+    fn challenge() -> HeaderValue {
+        HeaderValue::from_static("Bearer")
+    }
+}
+
+impl fmt::Debug for Bearer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bearer").finish_non_exhaustive()
+    }
+}
+
+/// Credentials of the `Basic` scheme.
+///
+/// The header value is base64 decoded and split into the username and password at the first
+/// `:`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Basic {
+    username: String,
+    password: String,
+}
+
+impl Basic {
+    /// Create credentials from a username and password.
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// The username.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// The password.
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+impl Credentials for Basic {
+    const SCHEME: &'static str = "Basic";
+
+    fn decode(value: &str) -> Option<Self> {
+        let decoded = STANDARD.decode(value).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+        Some(Self::new(username, password))
+    }
+
+    fn challenge() -> HeaderValue {
+        HeaderValue::from_static("Basic realm=\"Restricted\", charset=\"UTF-8\"")
+    }
+}
+
+impl fmt::Debug for Basic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Basic")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Extractor for the credentials in the `Authorization` header.
+///
+/// See the [module docs](self) for an example.
+#[derive(Debug, Clone)]
+pub struct Authorization<C>(pub C);
+
+#[async_trait]
+impl<C, S> FromRequestParts<S> for Authorization<C>
+where
+    C: Credentials,
+    S: Send + Sync,
+{
+    type Rejection = AuthorizationRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let value = parts
+            .headers
+            .get(AUTHORIZATION)
+            .ok_or_else(|| AuthorizationRejection::new::<C>(ErrorKind::Missing))?;
+
+        value
+            .to_str()
+            .ok()
+            .and_then(|value| {
+                let (scheme, credentials) = value.split_once(' ')?;
+                scheme
+                    .eq_ignore_ascii_case(C::SCHEME)
+                    .then(|| C::decode(credentials.trim()))?
+            })
+            .map(Self)
+            .ok_or_else(|| AuthorizationRejection::new::<C>(ErrorKind::Malformed))
+    }
+}
+
+saas_core::__impl_deref!(Authorization);
+
+/// Verifies credentials for [`RequireAuth`].
+///
+/// Implement it for your state, or for a type that can be extracted from it with
+/// [`FromRef`].
+#[async_trait]
+pub trait VerifyCredentials<C>: Send + Sync
+where
+    C: Credentials,
+{
+    /// Returns `true` if the credentials are valid.
+    async fn verify(&self, credentials: &C) -> bool;
+}
+
+/// Extractor that requires valid credentials.
+///
+/// The credentials are extracted with [`Authorization`] and checked with the
+/// [`VerifyCredentials`] implementation `V`, which is extracted from the state. It's mostly
+/// useful as middleware:
+///
+/// ```rust,no_run
+/// use saas::{
+///     async_trait,
+///     auth::{Bearer, RequireAuth, VerifyCredentials},
+///     middleware,
+///     routing::get,
+///     Router,
+/// };
+/// use std::{collections::HashSet, sync::Arc};
+///
+/// #[derive(Clone)]
+/// struct ApiKeys(Arc<HashSet<String>>);
+///
+/// #[async_trait]
+/// impl VerifyCredentials<Bearer> for ApiKeys {
+///     async fn verify(&self, credentials: &Bearer) -> bool {
+///         self.0.contains(credentials.token())
+///     }
+/// }
+///
+/// let keys = ApiKeys(Arc::new(HashSet::from(["secret".to_owned()])));
+///
+/// let app = Router::new()
+///     .route("/", get(|| async { "Hello, authenticated user!" }))
+///     .route_layer(middleware::from_extractor_with_state::<
+///         RequireAuth<Bearer, ApiKeys>,
+///         _,
+///     >(keys.clone()))
+///     .with_state(keys);
+/// # let _: Router = app;
+/// ```
+///
+/// Credentials that fail verification are rejected with `401 Unauthorized` and the same
+/// `WWW-Authenticate` challenge as missing credentials.
+pub struct RequireAuth<C, V>(pub C, PhantomData<fn() -> V>);
+
+impl<C, V> RequireAuth<C, V> {
+    /// Get the verified credentials.
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
+
+impl<C, V> fmt::Debug for RequireAuth<C, V>
+where
+    C: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RequireAuth").field(&self.0).finish()
+    }
+}
+
+impl<C, V> Clone for RequireAuth<C, V>
+where
+    C: Clone,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+}
+
+#[async_trait]
+impl<C, V, S> FromRequestParts<S> for RequireAuth<C, V>
+where
+    C: Credentials,
+    V: VerifyCredentials<C> + FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthorizationRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Authorization(credentials) = Authorization::<C>::from_request_parts(parts, state).await?;
+
+        if V::from_ref(state).verify(&credentials).await {
+            Ok(Self(credentials, PhantomData))
+        } else {
+            Err(AuthorizationRejection::new::<C>(ErrorKind::Invalid))
+        }
+    }
+}
+
+/// Rejection used for [`Authorization`] and [`RequireAuth`].
+///
+/// Always responds with `401 Unauthorized` and a `WWW-Authenticate` header with the
+/// [challenge](Credentials::challenge) of the scheme.
+#[derive(Debug)]
+pub struct AuthorizationRejection {
+    kind: ErrorKind,
+    challenge: HeaderValue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    Missing,
+    Malformed,
+    Invalid,
+}
+
+impl AuthorizationRejection {
+    fn new<C>(kind: ErrorKind) -> Self
+    where
+        C: Credentials,
+    {
+        Self {
+            kind,
+            challenge: C::challenge(),
+        }
+    }
+
+    /// Returns `true` if the request didn't have an `Authorization` header.
+    pub fn is_missing(&self) -> bool {
+        self.kind == ErrorKind::Missing
+    }
+
+    /// Get the response body text used for this rejection.
+    pub fn body_text(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get the status code used for this rejection.
+    pub fn status(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+}
+
+impl fmt::Display for AuthorizationRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Missing => f.write_str("Missing `Authorization` header"),
+            ErrorKind::Malformed => f.write_str("Malformed `Authorization` header"),
+            ErrorKind::Invalid => f.write_str("Invalid credentials"),
+        }
+    }
+}
+
+impl std::error::Error for AuthorizationRejection {}
+
+impl IntoResponse for AuthorizationRejection {
+    fn into_response(self) -> Response {
+        saas_core::__log_rejection!(
+            rejection_type = AuthorizationRejection,
+            body_text = self.body_text(),
+            status = self.status(),
+        );
+        let status = self.status();
+        let body_text = self.body_text();
+        let name = match self.kind {
+            ErrorKind::Missing => "MissingAuthorizationHeader",
+            ErrorKind::Malformed => "MalformedAuthorizationHeader",
+            ErrorKind::Invalid => "InvalidCredentials",
+        };
+        let mut res = (
+            status,
+            [(WWW_AUTHENTICATE, self.challenge.clone())],
+            body_text.clone(),
+        )
+            .into_response();
+        res.extensions_mut().insert(
+            AnyRejection::__new(status, body_text.clone(), self)
+                .__with_message(name, "Unauthorized", Some(body_text)),
+        );
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Request;
+
+    async fn authorization<C>(value: Option<&str>) -> Result<C, AuthorizationRejection>
+    where
+        C: Credentials,
+    {
+        let mut req = Request::builder();
+        if let Some(value) = value {
+            req = req.header(AUTHORIZATION, value);
+        }
+        let (mut parts, _) = req.body(()).unwrap().into_parts();
+        Authorization::<C>::from_request_parts(&mut parts, &())
+            .await
+            .map(|Authorization(credentials)| credentials)
+    }
+
+    #[tokio::test]
+    async fn basic() {
+        let basic = authorization::<Basic>(Some("Basic dXNlcjpwYXNzOndvcmQ="))
+            .await
+            .unwrap();
+        assert_eq!(basic, Basic::new("user", "pass:word"));
+
+        let rejection = authorization::<Basic>(Some("Basic not base64")).await.unwrap_err();
+        assert!(!rejection.is_missing());
+
+        let res = authorization::<Basic>(None).await.unwrap_err().into_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers()[WWW_AUTHENTICATE],
+            "Basic realm=\"Restricted\", charset=\"UTF-8\""
+        );
+    }
+
+    #[tokio::test]
+    async fn bearer() {
+        let bearer = authorization::<Bearer>(Some("bearer abc.DEF-123="))
+            .await
+            .unwrap();
+        assert_eq!(bearer.token(), "abc.DEF-123=");
+
+        let rejection = authorization::<Bearer>(Some("Basic dXNlcjpwYXNz"))
+            .await
+            .unwrap_err();
+        assert!(!rejection.is_missing());
+        assert_eq!(rejection.into_response().headers()[WWW_AUTHENTICATE], "Bearer");
+    }
+}
@@ -0,0 +1,206 @@
+use crate::extract::Request;
+use crate::extract::{rejection::*, FromRequest};
+use async_trait::async_trait;
+use bytes::{BufMut, Bytes, BytesMut};
+use http::{
+    header::{self, HeaderMap, HeaderValue},
+    StatusCode,
+};
+use saas_core::response::{IntoResponse, Response};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// CBOR extractor and response.
+///
+/// Works the same way as [`Json`](crate::Json) but for [CBOR] bodies.
+///
+/// # As extractor
+///
+/// The request is rejected if it doesn't have a `Content-Type` of `application/cbor` or
+/// `application/*+cbor`, if the body isn't valid CBOR, or if it can't be deserialized into the
+/// target type.
+///
+/// ```rust,no_run
+/// use saas::{routing::post, Cbor, Router};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Reading {
+///     sensor: String,
+///     value: f64,
+/// }
+///
+/// async fn record(Cbor(reading): Cbor<Reading>) {
+///     // ...
+/// }
+///
+/// let app = Router::new().route("/readings", post(record));
+/// # let _: Router = app;
+/// ```
+///
+/// # As response
+///
+/// The value is encoded with `Content-Type: application/cbor`.
+///
+/// [CBOR]: https://cbor.io
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+#[derive(Debug, Clone, Copy, Default)]
+#[must_use]
+pub struct Cbor<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Cbor<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = CborRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if cbor_content_type(req.headers()) {
+            let bytes = Bytes::from_request(req, state).await?;
+            Self::from_bytes(&bytes)
+        } else {
+            Err(MissingCborContentType.into())
+        }
+    }
+}
+
+impl<T> Cbor<T>
+where
+    T: DeserializeOwned,
+{
+    /// Construct a `Cbor<T>` from a byte slice.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CborRejection> {
+        let mut reader = bytes;
+
+        match ciborium::de::from_reader(&mut reader) {
+            // the body must contain a single value
+            Ok(_) if !reader.is_empty() => {
+                Err(CborSyntaxError::from_err("trailing bytes after the CBOR value").into())
+            }
+            Ok(value) => Ok(Cbor(value)),
+            Err(err @ ciborium::de::Error::Semantic(..)) => {
+                Err(CborDataError::from_err(err).into())
+            }
+            Err(err) => Err(CborSyntaxError::from_err(err).into()),
+        }
+    }
+}
+
+fn cbor_content_type(headers: &HeaderMap) -> bool {
+    let mime = match headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.parse::<mime::Mime>().ok())
+    {
+        Some(mime) => mime,
+        None => return false,
+    };
+
+    is_cbor(&mime)
+}
+
+pub(crate) fn is_cbor(mime: &mime::Mime) -> bool {
+    mime.type_() == "application"
+        && (mime.subtype() == "cbor" || mime.suffix().is_some_and(|name| name == "cbor"))
+}
+
+saas_core::__impl_deref!(Cbor);
+
+impl<T> From<T> for Cbor<T> {
+    fn from(inner: T) -> Self {
+        Self(inner)
+    }
+}
+
+impl<T> IntoResponse for Cbor<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        let mut buf = BytesMut::with_capacity(128).writer();
+
+        match ciborium::ser::into_writer(&self.0, &mut buf) {
+            Ok(()) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/cbor"),
+                )],
+                buf.into_inner().freeze(),
+            )
+                .into_response(),
+
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(mime::TEXT_PLAIN_UTF_8.as_ref()),
+                )],
+                err.to_string(),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Reading {
+        sensor: String,
+        value: u32,
+    }
+
+    fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(value, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn content_type() {
+        let mut headers = HeaderMap::new();
+        for content_type in ["application/cbor", "application/vnd.foo+cbor"] {
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            assert!(cbor_content_type(&headers));
+        }
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        assert!(!cbor_content_type(&headers));
+    }
+
+    #[test]
+    fn data_and_syntax_errors() {
+        let reading = Reading {
+            sensor: "a".to_owned(),
+            value: 1,
+        };
+        let bytes = encode(&reading);
+        assert_eq!(Cbor::<Reading>::from_bytes(&bytes).unwrap().0, reading);
+
+        let bytes = encode(&"not a reading");
+        assert!(matches!(
+            Cbor::<Reading>::from_bytes(&bytes),
+            Err(CborRejection::CborDataError(_))
+        ));
+
+        assert!(matches!(
+            Cbor::<Reading>::from_bytes(&[0xff]),
+            Err(CborRejection::CborSyntaxError(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        let mut bytes = encode(&1u32);
+        assert_eq!(Cbor::<u32>::from_bytes(&bytes).unwrap().0, 1);
+
+        bytes.extend(encode(&2u32));
+        assert!(matches!(
+            Cbor::<u32>::from_bytes(&bytes),
+            Err(CborRejection::CborSyntaxError(_))
+        ));
+    }
+}
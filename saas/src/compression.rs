@@ -0,0 +1,594 @@
+//! Compression of response bodies.
+//!
+//! [`CompressionLayer`] compresses response bodies with the encoding the client prefers in its
+//! `Accept-Encoding` header, one of `br`, `zstd`, `gzip` or `deflate`:
+//!
+//! ```rust,no_run
+//! use saas::{compression::CompressionLayer, routing::get, Router};
+//!
+//! async fn users() -> String {
+//!     // a large body...
+//!     # String::new()
+//! }
+//!
+//! let app = Router::new()
+//!     .route("/users", get(users))
+//!     .layer(CompressionLayer::new());
+//! # let _: Router = app;
+//! ```
+//!
+//! Bodies are compressed while they are streamed. For server-sent events, such as
+//! [`Sse`](crate::response::sse::Sse) responses, the encoder is flushed after each event so
+//! clients receive events as they are sent.
+//!
+//! Responses are left as they are when they
+//!
+//! - already have a `Content-Encoding`,
+//! - are smaller than [`CompressionLayer::min_size`], if their size is known,
+//! - have a content type that is already compressed, such as images, audio, video and archives,
+//!   or one added with [`CompressionLayer::skip_content_type`],
+//! - are partial content, or have `Cache-Control: no-transform`.
+//!
+//! Other responses get `Vary: Accept-Encoding`, also when the client doesn't accept any of the
+//! enabled encodings, so caches store the compressed and uncompressed variants separately.
+//!
+//! # Per route policies
+//!
+//! A `CompressionLayer` added with [`route_layer`](crate::Router::route_layer) or to a single
+//! [`MethodRouter`](crate::routing::MethodRouter) takes precedence over layers further out, so
+//! routes can use a different policy, or opt out with [`CompressionLayer::none`]:
+//!
+//! ```rust,no_run
+//! use saas::{compression::CompressionLayer, routing::get, Router};
+//!
+//! async fn download() {}
+//! async fn events() {}
+//! async fn index() {}
+//!
+//! let app = Router::new()
+//!     .route("/download", get(download))
+//!     .route_layer(CompressionLayer::none())
+//!     .route("/events", get(events).layer(CompressionLayer::new().br(false)))
+//!     .route("/", get(index))
+//!     .layer(CompressionLayer::new());
+//! # let _: Router = app;
+//! ```
+
+use crate::content_coding::{Encoding, Encodings};
+use async_compression::{
+    tokio::write::{BrotliEncoder, GzipEncoder, ZlibEncoder, ZstdEncoder},
+    Level,
+};
+use futures_util::{ready, stream, TryStreamExt};
+use http::{header, HeaderMap, HeaderValue, Request, StatusCode};
+use http_body::Body as _;
+use pin_project_lite::pin_project;
+use saas_core::{body::Body, response::Response, BoxError};
+use std::{
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Responses with a known size below this aren't compressed by default.
+const DEFAULT_MIN_SIZE: u64 = 1024;
+
+/// Layer that compresses response bodies.
+///
+/// All encodings are enabled by default. See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct CompressionLayer {
+    policy: Policy,
+}
+
+#[derive(Debug, Clone)]
+struct Policy {
+    encodings: Encodings,
+    min_size: u64,
+    skip_content_types: Arc<Vec<String>>,
+}
+
+// Inserted into responses once a `Compression` has applied its policy, so layers further out
+// leave them alone.
+#[derive(Debug, Clone, Copy)]
+struct CompressionHandled;
+
+impl CompressionLayer {
+    /// Create a new `CompressionLayer` with all encodings enabled.
+    pub fn new() -> Self {
+        Self::with_encodings(Encodings::ALL)
+    }
+
+    /// Create a `CompressionLayer` with all encodings disabled.
+    ///
+    /// Responses are left uncompressed, also by `CompressionLayer`s further out. Encodings can be
+    /// enabled again with the other methods.
+    pub fn none() -> Self {
+        Self::with_encodings(Encodings::NONE)
+    }
+
+    fn with_encodings(encodings: Encodings) -> Self {
+        Self {
+            policy: Policy {
+                encodings,
+                min_size: DEFAULT_MIN_SIZE,
+                skip_content_types: Arc::new(Vec::new()),
+            },
+        }
+    }
+
+    /// Enable or disable `gzip`.
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.policy.encodings.gzip = enable;
+        self
+    }
+
+    /// Enable or disable `deflate`.
+    pub fn deflate(mut self, enable: bool) -> Self {
+        self.policy.encodings.deflate = enable;
+        self
+    }
+
+    /// Enable or disable `br`.
+    pub fn br(mut self, enable: bool) -> Self {
+        self.policy.encodings.br = enable;
+        self
+    }
+
+    /// Enable or disable `zstd`.
+    pub fn zstd(mut self, enable: bool) -> Self {
+        self.policy.encodings.zstd = enable;
+        self
+    }
+
+    /// Don't compress responses with a known size of less than `min_size` bytes.
+    ///
+    /// Defaults to 1024 bytes. Streaming responses of unknown size are always compressed.
+    pub fn min_size(mut self, min_size: u64) -> Self {
+        self.policy.min_size = min_size;
+        self
+    }
+
+    /// Don't compress responses with the given `Content-Type`.
+    ///
+    /// `content_type` is either a full media type such as `application/pdf`, or a type with a
+    /// wildcard subtype such as `font/*`. This is in addition to the content types that are
+    /// already compressed, which are always skipped.
+    pub fn skip_content_type(mut self, content_type: &str) -> Self {
+        let content_type = content_type.trim().to_ascii_lowercase();
+        let skip_content_types = Arc::make_mut(&mut self.policy.skip_content_types);
+        if !skip_content_types.contains(&content_type) {
+            skip_content_types.push(content_type);
+        }
+        self
+    }
+}
+
+impl Default for CompressionLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for CompressionLayer {
+    type Service = Compression<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Compression {
+            inner,
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+/// Middleware that compresses response bodies.
+///
+/// Created with [`CompressionLayer`].
+#[derive(Debug, Clone)]
+pub struct Compression<S> {
+    inner: S,
+    policy: Policy,
+}
+
+impl<B, S> Service<Request<B>> for Compression<S>
+where
+    S: Service<Request<B>, Response = Response>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let encoding = self.policy.encodings.negotiate(req.headers());
+        ResponseFuture {
+            future: self.inner.call(req),
+            encoding,
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`Compression`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        future: F,
+        encoding: Option<Encoding>,
+        policy: Policy,
+    }
+}
+
+impl<F> fmt::Debug for ResponseFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish_non_exhaustive()
+    }
+}
+
+impl<F, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response, E>>,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.future.poll(cx))?;
+        Poll::Ready(Ok(compress_response(res, *this.encoding, this.policy)))
+    }
+}
+
+fn compress_response(mut res: Response, encoding: Option<Encoding>, policy: &Policy) -> Response {
+    if res.extensions().get::<CompressionHandled>().is_some() {
+        return res;
+    }
+    res.extensions_mut().insert(CompressionHandled);
+
+    if policy.encodings.is_empty() || !policy.is_compressible(&res) {
+        return res;
+    }
+
+    add_vary(res.headers_mut());
+
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None => return res,
+    };
+
+    let headers = res.headers_mut();
+    headers.insert(
+        header::CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    headers.remove(header::CONTENT_LENGTH);
+    headers.remove(header::ACCEPT_RANGES);
+    weaken_etag(headers);
+
+    let flush = is_event_stream(res.headers());
+    res.map(|body| compress(body, encoding, flush))
+}
+
+impl Policy {
+    fn is_compressible(&self, res: &Response) -> bool {
+        let status = res.status();
+        if status == StatusCode::NO_CONTENT
+            || status == StatusCode::NOT_MODIFIED
+            || status == StatusCode::PARTIAL_CONTENT
+            || status.is_informational()
+        {
+            return false;
+        }
+
+        let headers = res.headers();
+        if headers.contains_key(header::CONTENT_ENCODING)
+            || headers.contains_key(header::CONTENT_RANGE)
+            || has_no_transform(headers)
+        {
+            return false;
+        }
+
+        if let Some(size) = body_size(res) {
+            if size < self.min_size {
+                return false;
+            }
+        }
+
+        match content_type(headers) {
+            Some(mime) => !is_compressed_type(&mime) && !self.skips(&mime),
+            None => true,
+        }
+    }
+
+    fn skips(&self, mime: &mime::Mime) -> bool {
+        let essence = mime.essence_str();
+        self.skip_content_types.iter().any(|content_type| {
+            content_type == essence
+                || content_type
+                    .strip_suffix("/*")
+                    .is_some_and(|type_| type_ == mime.type_().as_str())
+        })
+    }
+}
+
+fn body_size(res: &Response) -> Option<u64> {
+    let size_hint = res.body().size_hint();
+    if let Some(size) = size_hint.exact() {
+        return Some(size);
+    }
+    res.headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+fn content_type(headers: &HeaderMap) -> Option<mime::Mime> {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+// content types that are already compressed and don't get smaller
+fn is_compressed_type(mime: &mime::Mime) -> bool {
+    match mime.type_().as_str() {
+        "image" => mime.subtype() != mime::SVG,
+        "audio" | "video" => true,
+        _ => matches!(
+            mime.essence_str(),
+            "application/gzip"
+                | "application/x-gzip"
+                | "application/zip"
+                | "application/zstd"
+                | "application/x-bzip2"
+                | "application/x-7z-compressed"
+                | "application/x-xz"
+                | "font/woff"
+                | "font/woff2"
+        ),
+    }
+}
+
+fn is_event_stream(headers: &HeaderMap) -> bool {
+    content_type(headers).is_some_and(|mime| {
+        mime.essence_str() == mime::TEXT_EVENT_STREAM.essence_str()
+    })
+}
+
+fn has_no_transform(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"))
+}
+
+fn add_vary(headers: &mut HeaderMap) {
+    let varies = headers
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|name| {
+            let name = name.trim();
+            name == "*" || name.eq_ignore_ascii_case("accept-encoding")
+        });
+    if !varies {
+        headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+}
+
+// the compressed body isn't byte for byte the same representation anymore
+fn weaken_etag(headers: &mut HeaderMap) {
+    let weak = match headers.get(header::ETAG) {
+        Some(etag) if !etag.as_bytes().starts_with(b"W/") => {
+            let mut weak = b"W/".to_vec();
+            weak.extend_from_slice(etag.as_bytes());
+            HeaderValue::from_bytes(&weak).ok()
+        }
+        _ => None,
+    };
+    if let Some(weak) = weak {
+        headers.insert(header::ETAG, weak);
+    }
+}
+
+fn compress(body: Body, encoding: Encoding, flush: bool) -> Body {
+    let encoder = Encoder::new(encoding);
+    let stream = stream::try_unfold(
+        (body, Some(encoder)),
+        move |(mut body, encoder)| async move {
+            let mut encoder = match encoder {
+                Some(encoder) => encoder,
+                None => return Ok::<_, BoxError>(None),
+            };
+            loop {
+                match body.try_next().await? {
+                    Some(chunk) => {
+                        let writer = encoder.writer();
+                        writer.write_all(&chunk).await?;
+                        if flush {
+                            // send each event as soon as it is produced
+                            writer.flush().await?;
+                        }
+                        let output = encoder.take_output();
+                        if !output.is_empty() {
+                            return Ok(Some((output, (body, Some(encoder)))));
+                        }
+                    }
+                    None => {
+                        encoder.writer().shutdown().await?;
+                        let output = encoder.take_output();
+                        return Ok(Some((output, (body, None))));
+                    }
+                }
+            }
+        },
+    );
+    Body::from_stream(stream)
+}
+
+enum Encoder {
+    Gzip(GzipEncoder<Vec<u8>>),
+    Deflate(ZlibEncoder<Vec<u8>>),
+    // the brotli state is several kilobytes
+    Br(Box<BrotliEncoder<Vec<u8>>>),
+    Zstd(ZstdEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+    fn new(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Gzip => Self::Gzip(GzipEncoder::new(Vec::new())),
+            Encoding::Deflate => Self::Deflate(ZlibEncoder::new(Vec::new())),
+            // the default quality is too slow to compress responses on the fly
+            Encoding::Br => Self::Br(Box::new(BrotliEncoder::with_quality(
+                Vec::new(),
+                Level::Precise(4),
+            ))),
+            Encoding::Zstd => Self::Zstd(ZstdEncoder::new(Vec::new())),
+        }
+    }
+
+    fn writer(&mut self) -> &mut (dyn AsyncWrite + Unpin + Send) {
+        match self {
+            Self::Gzip(encoder) => encoder,
+            Self::Deflate(encoder) => encoder,
+            Self::Br(encoder) => &mut **encoder,
+            Self::Zstd(encoder) => encoder,
+        }
+    }
+
+    fn take_output(&mut self) -> Vec<u8> {
+        let output = match self {
+            Self::Gzip(encoder) => encoder.get_mut(),
+            Self::Deflate(encoder) => encoder.get_mut(),
+            Self::Br(encoder) => encoder.get_mut(),
+            Self::Zstd(encoder) => encoder.get_mut(),
+        };
+        mem::take(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        response::{
+            sse::{Event, Sse},
+            IntoResponse,
+        },
+        routing::get,
+        Router,
+    };
+    use async_compression::tokio::bufread::GzipDecoder;
+    use futures_util::StreamExt;
+    use std::convert::Infallible;
+    use tokio::io::AsyncReadExt;
+    use tower::ServiceExt;
+
+    async fn gunzip(data: &[u8]) -> String {
+        let mut decoder = GzipDecoder::new(data);
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).await.unwrap();
+        decoded
+    }
+
+    fn get_gzip(uri: &str) -> Request<Body> {
+        Request::get(uri)
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn compresses_body() {
+        let app = Router::new()
+            .route("/", get(|| async { "a".repeat(2048) }))
+            .layer(CompressionLayer::new());
+
+        let res = app.oneshot(get_gzip("/")).await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(res.headers()[header::VARY], "accept-encoding");
+        assert!(res.headers().get(header::CONTENT_LENGTH).is_none());
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(body.len() < 2048);
+        assert_eq!(gunzip(&body).await, "a".repeat(2048));
+    }
+
+    #[tokio::test]
+    async fn skips_small_bodies_and_compressed_types() {
+        let app = Router::new()
+            .route("/small", get(|| async { "small" }))
+            .route(
+                "/image",
+                get(|| async { ([(header::CONTENT_TYPE, "image/png")], vec![0; 2048]) }),
+            )
+            .layer(CompressionLayer::new());
+
+        let res = app.clone().oneshot(get_gzip("/small")).await.unwrap();
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+
+        let res = app.oneshot(get_gzip("/image")).await.unwrap();
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+        assert!(res.headers().get(header::VARY).is_none());
+    }
+
+    #[tokio::test]
+    async fn varies_without_accepted_encoding() {
+        let app = Router::new()
+            .route("/", get(|| async { "a".repeat(2048) }))
+            .layer(CompressionLayer::new().gzip(false));
+
+        let res = app.oneshot(get_gzip("/")).await.unwrap();
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(res.headers()[header::VARY], "accept-encoding");
+    }
+
+    #[tokio::test]
+    async fn flushes_each_event() {
+        let events = stream::iter([Ok::<_, Infallible>(Event::default().data("hello"))])
+            .chain(stream::pending());
+        let res = compress_response(
+            Sse::new(events).into_response(),
+            Some(Encoding::Gzip),
+            &CompressionLayer::new().policy,
+        );
+        assert_eq!(res.headers()[header::CONTENT_ENCODING], "gzip");
+
+        // the stream never ends, so the event is only received if it was flushed
+        let mut body = res.into_body();
+        let chunk = body.try_next().await.unwrap().unwrap();
+        let mut decoder = GzipDecoder::new(&chunk[..]);
+        let mut buf = [0; 64];
+        let n = decoder.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"data:hello\n\n");
+    }
+
+    #[tokio::test]
+    async fn route_layer_takes_precedence() {
+        let app = Router::new()
+            .route("/plain", get(|| async { "a".repeat(2048) }))
+            .route_layer(CompressionLayer::none())
+            .route("/", get(|| async { "a".repeat(2048) }))
+            .layer(CompressionLayer::new());
+
+        let res = app.clone().oneshot(get_gzip("/plain")).await.unwrap();
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+
+        let res = app.oneshot(get_gzip("/")).await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_ENCODING], "gzip");
+    }
+}
@@ -0,0 +1,719 @@
+//! Conditional requests with `ETag` and `Last-Modified`.
+//!
+//! Handlers send validators with the [`ETag`] and [`LastModified`] response parts.
+//! [`ConditionalRequestLayer`] evaluates `If-Match`, `If-None-Match`, `If-Modified-Since` and
+//! `If-Unmodified-Since` against them:
+//!
+//! ```rust,no_run
+//! use saas::{
+//!     conditional::{ConditionalRequestLayer, ETag},
+//!     routing::get,
+//!     Router,
+//! };
+//!
+//! async fn report() -> (ETag, &'static str) {
+//!     (ETag::strong("v42"), "...")
+//! }
+//!
+//! let app = Router::new()
+//!     .route("/report", get(report))
+//!     .layer(ConditionalRequestLayer::new());
+//! # let _: Router = app;
+//! ```
+//!
+//! A `GET` or `HEAD` request with a matching `If-None-Match` gets `304 Not Modified`, and
+//! one with a failed `If-Match` gets `412 Precondition Failed`.
+//!
+//! # Unsafe methods
+//!
+//! The validators in the response to an unsafe request, such as `PUT`, describe the resource
+//! after the change, so they can't be used to evaluate its preconditions, and checking them
+//! in a separate request wouldn't stop a concurrent change between the check and the write.
+//! The middleware passes unsafe requests through unchanged.
+//!
+//! Handlers check their preconditions themselves with the [`Preconditions`] extractor, against
+//! the validators they read in the same transaction as the write:
+//!
+//! ```rust,no_run
+//! use saas::{
+//!     conditional::{ETag, PreconditionFailed, Preconditions},
+//!     extract::Path,
+//!     routing::put,
+//!     Router,
+//! };
+//!
+//! async fn update_document(
+//!     Path(id): Path<u64>,
+//!     preconditions: Preconditions,
+//!     body: String,
+//! ) -> Result<ETag, PreconditionFailed> {
+//!     let version = current_version(id).await;
+//!     preconditions.check(Some(&ETag::strong(version.to_string())), None)?;
+//!
+//!     // nobody else changed the document since the client fetched it
+//!     let version = save(id, body).await;
+//!     Ok(ETag::strong(version.to_string()))
+//! }
+//! # async fn current_version(id: u64) -> u64 { 0 }
+//! # async fn save(id: u64, body: String) -> u64 { 0 }
+//!
+//! let app = Router::new().route("/documents/:id", put(update_document));
+//! # let _: Router = app;
+//! ```
+
+use crate::extract::FromRequestParts;
+use async_trait::async_trait;
+use futures_util::ready;
+use http::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    request::Parts,
+    Method, Request, StatusCode,
+};
+use pin_project_lite::pin_project;
+use saas_core::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
+use std::{
+    convert::Infallible,
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    str::FromStr,
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// An entity tag, sent as the `ETag` response header.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag {
+    tag: String,
+    weak: bool,
+}
+
+impl ETag {
+    /// Create a strong entity tag, for representations that are byte-for-byte identical.
+    ///
+    /// # Panics
+    ///
+    /// If `tag` contains `"`, whitespace or control characters.
+    pub fn strong(tag: impl Into<String>) -> Self {
+        Self::new(tag.into(), false)
+    }
+
+    /// Create a weak entity tag, for representations that are semantically equivalent.
+    ///
+    /// # Panics
+    ///
+    /// If `tag` contains `"`, whitespace or control characters.
+    pub fn weak(tag: impl Into<String>) -> Self {
+        Self::new(tag.into(), true)
+    }
+
+    fn new(tag: String, weak: bool) -> Self {
+        assert!(
+            tag.bytes().all(is_etagc),
+            "invalid entity tag `{}`, it can't contain `\"`, whitespace or control characters",
+            tag
+        );
+        Self { tag, weak }
+    }
+
+    /// The opaque tag, without quotes.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Returns `true` if this is a weak entity tag.
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Strong comparison, where both tags must be strong and equal.
+    pub fn strong_eq(&self, other: &Self) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison, where the tags must be equal but may be weak.
+    pub fn weak_eq(&self, other: &Self) -> bool {
+        self.tag == other.tag
+    }
+
+    fn to_header_value(&self) -> HeaderValue {
+        HeaderValue::try_from(self.to_string()).expect("entity tags are valid header values")
+    }
+}
+
+fn is_etagc(byte: u8) -> bool {
+    byte == 0x21 || (0x23..=0x7e).contains(&byte) || byte >= 0x80
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+impl FromStr for ETag {
+    type Err = InvalidETag;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_etag(s.trim()) {
+            Some((etag, "")) => Ok(etag),
+            _ => Err(InvalidETag),
+        }
+    }
+}
+
+// parse one entity tag from the start of `s`, returning it and the rest of `s`
+fn parse_etag(s: &str) -> Option<(ETag, &str)> {
+    let (weak, s) = match s.strip_prefix("W/") {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let s = s.strip_prefix('"')?;
+    let end = s.find('"')?;
+    let tag = &s[..end];
+    tag.bytes().all(is_etagc).then(|| {
+        (
+            ETag {
+                tag: tag.to_owned(),
+                weak,
+            },
+            &s[end + 1..],
+        )
+    })
+}
+
+impl IntoResponseParts for ETag {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        res.headers_mut().insert(header::ETAG, self.to_header_value());
+        Ok(res)
+    }
+}
+
+impl IntoResponse for ETag {
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+/// Error returned when parsing an [`ETag`] fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InvalidETag;
+
+impl fmt::Display for InvalidETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid entity tag")
+    }
+}
+
+impl Error for InvalidETag {}
+
+/// The modification time of a representation, sent as the `Last-Modified` response header.
+///
+/// HTTP dates have a resolution of one second, so sub-second precision is ignored when
+/// comparing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastModified(pub SystemTime);
+
+impl IntoResponseParts for LastModified {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let value = HeaderValue::try_from(httpdate::fmt_http_date(self.0))
+            .expect("HTTP dates are valid header values");
+        res.headers_mut().insert(header::LAST_MODIFIED, value);
+        Ok(res)
+    }
+}
+
+impl IntoResponse for LastModified {
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+/// Extractor for the preconditions of a request.
+///
+/// Used to check the preconditions of unsafe requests, see the
+/// [module docs](self#unsafe-methods) for an example.
+#[derive(Debug, Clone)]
+pub struct Preconditions {
+    method: Method,
+    if_match: Option<EntityTags>,
+    if_none_match: Option<EntityTags>,
+    if_modified_since: Option<SystemTime>,
+    if_unmodified_since: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone)]
+enum EntityTags {
+    Any,
+    List(Vec<ETag>),
+}
+
+impl EntityTags {
+    fn parse(headers: &HeaderMap, name: HeaderName) -> Option<Self> {
+        let mut tags = Vec::new();
+        for value in headers.get_all(&name) {
+            // a malformed list doesn't match anything
+            let mut rest = match value.to_str() {
+                Ok(value) => value,
+                Err(_) => return Some(Self::List(Vec::new())),
+            };
+            loop {
+                rest = rest.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+                if rest.is_empty() {
+                    break;
+                }
+                if let Some(after) = rest.strip_prefix('*') {
+                    if after.trim().is_empty() {
+                        return Some(Self::Any);
+                    }
+                }
+                match parse_etag(rest) {
+                    Some((etag, after)) => {
+                        tags.push(etag);
+                        rest = after;
+                    }
+                    None => return Some(Self::List(Vec::new())),
+                }
+            }
+        }
+
+        headers.contains_key(&name).then_some(Self::List(tags))
+    }
+
+    fn matches(&self, current: Option<&Validators<'_>>, eq: fn(&ETag, &ETag) -> bool) -> bool {
+        match (self, current) {
+            (Self::Any, Some(_)) => true,
+            (Self::List(tags), Some(current)) => current
+                .etag
+                .is_some_and(|etag| tags.iter().any(|tag| eq(tag, etag))),
+            (_, None) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Validators<'a> {
+    etag: Option<&'a ETag>,
+    last_modified: Option<SystemTime>,
+}
+
+impl Preconditions {
+    fn from_headers(method: Method, headers: &HeaderMap) -> Self {
+        let date = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| httpdate::parse_http_date(value).ok())
+        };
+
+        Self {
+            method,
+            if_match: EntityTags::parse(headers, header::IF_MATCH),
+            if_none_match: EntityTags::parse(headers, header::IF_NONE_MATCH),
+            if_modified_since: date(header::IF_MODIFIED_SINCE),
+            if_unmodified_since: date(header::IF_UNMODIFIED_SINCE),
+        }
+    }
+
+    /// Returns `true` if the request has any preconditions.
+    pub fn is_conditional(&self) -> bool {
+        self.if_match.is_some()
+            || self.if_none_match.is_some()
+            || self.if_modified_since.is_some()
+            || self.if_unmodified_since.is_some()
+    }
+
+    /// Check the preconditions against the current validators of the resource.
+    ///
+    /// Fails with `304 Not Modified` for `GET` and `HEAD` requests whose cached
+    /// representation is still current, and with `412 Precondition Failed` otherwise.
+    pub fn check(
+        &self,
+        etag: Option<&ETag>,
+        last_modified: Option<SystemTime>,
+    ) -> Result<(), PreconditionFailed> {
+        self.evaluate(Some(&Validators {
+            etag,
+            last_modified,
+        }))
+    }
+
+    /// Check the preconditions for a resource that doesn't exist.
+    ///
+    /// For example `If-None-Match: *`, used to only create a resource that doesn't exist yet,
+    /// passes, while any `If-Match` fails.
+    pub fn check_absent(&self) -> Result<(), PreconditionFailed> {
+        self.evaluate(None)
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-13.2.2
+    fn evaluate(&self, current: Option<&Validators<'_>>) -> Result<(), PreconditionFailed> {
+        let safe = self.method == Method::GET || self.method == Method::HEAD;
+        let last_modified = current.and_then(|current| current.last_modified);
+
+        if let Some(if_match) = &self.if_match {
+            if !if_match.matches(current, ETag::strong_eq) {
+                return Err(PreconditionFailed::new(StatusCode::PRECONDITION_FAILED));
+            }
+        } else if let (Some(since), Some(last_modified)) = (self.if_unmodified_since, last_modified)
+        {
+            if unix_secs(last_modified) > unix_secs(since) {
+                return Err(PreconditionFailed::new(StatusCode::PRECONDITION_FAILED));
+            }
+        }
+
+        if let Some(if_none_match) = &self.if_none_match {
+            if if_none_match.matches(current, ETag::weak_eq) {
+                return Err(PreconditionFailed::new(if safe {
+                    StatusCode::NOT_MODIFIED
+                } else {
+                    StatusCode::PRECONDITION_FAILED
+                }));
+            }
+        } else if let (true, Some(since), Some(last_modified)) =
+            (safe, self.if_modified_since, last_modified)
+        {
+            if unix_secs(last_modified) <= unix_secs(since) {
+                return Err(PreconditionFailed::new(StatusCode::NOT_MODIFIED));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn unix_secs(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|time| time.as_secs())
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Preconditions
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(parts.method.clone(), &parts.headers))
+    }
+}
+
+/// A failed precondition.
+///
+/// Responds with either `304 Not Modified` or `412 Precondition Failed` and an empty body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreconditionFailed {
+    status: StatusCode,
+}
+
+impl PreconditionFailed {
+    fn new(status: StatusCode) -> Self {
+        Self { status }
+    }
+
+    /// Returns `true` if this is a `304 Not Modified`, rather than a failed precondition.
+    pub fn is_not_modified(&self) -> bool {
+        self.status == StatusCode::NOT_MODIFIED
+    }
+
+    /// Get the status code used for this response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+impl fmt::Display for PreconditionFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_not_modified() {
+            f.write_str("Not modified")
+        } else {
+            f.write_str("Precondition failed")
+        }
+    }
+}
+
+impl Error for PreconditionFailed {}
+
+impl IntoResponse for PreconditionFailed {
+    fn into_response(self) -> Response {
+        self.status.into_response()
+    }
+}
+
+/// Layer that evaluates conditional requests.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConditionalRequestLayer {
+    _priv: (),
+}
+
+impl ConditionalRequestLayer {
+    /// Create a new `ConditionalRequestLayer`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S> Layer<S> for ConditionalRequestLayer {
+    type Service = ConditionalRequest<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConditionalRequest { inner }
+    }
+}
+
+/// Middleware that evaluates conditional requests.
+///
+/// Created with [`ConditionalRequestLayer`].
+#[derive(Debug, Clone, Copy)]
+pub struct ConditionalRequest<S> {
+    inner: S,
+}
+
+// headers that are sent with a `304 Not Modified` as they would have been with a `200 OK`
+const NOT_MODIFIED_HEADERS: [HeaderName; 7] = [
+    header::CACHE_CONTROL,
+    header::CONTENT_LOCATION,
+    header::DATE,
+    header::ETAG,
+    header::EXPIRES,
+    header::LAST_MODIFIED,
+    header::VARY,
+];
+
+impl<B, S> Service<Request<B>> for ConditionalRequest<S>
+where
+    S: Service<Request<B>, Response = Response>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let safe = req.method() == Method::GET || req.method() == Method::HEAD;
+        let preconditions = Some(Preconditions::from_headers(req.method().clone(), req.headers()))
+            .filter(|preconditions| safe && preconditions.is_conditional());
+
+        ResponseFuture {
+            future: self.inner.call(req),
+            preconditions,
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`ConditionalRequest`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        future: F,
+        preconditions: Option<Preconditions>,
+    }
+}
+
+impl<F> fmt::Debug for ResponseFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish_non_exhaustive()
+    }
+}
+
+impl<F, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response, E>>,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.future.poll(cx))?;
+
+        let preconditions = match this.preconditions.take() {
+            Some(preconditions) if res.status().is_success() => preconditions,
+            _ => return Poll::Ready(Ok(res)),
+        };
+
+        let res = match preconditions.evaluate(Some(&validators(&res).borrowed())) {
+            Ok(()) => res,
+            Err(failed) if failed.is_not_modified() => not_modified(res),
+            Err(failed) => failed.into_response(),
+        };
+        Poll::Ready(Ok(res))
+    }
+}
+
+struct OwnedValidators {
+    etag: Option<ETag>,
+    last_modified: Option<SystemTime>,
+}
+
+impl OwnedValidators {
+    fn borrowed(&self) -> Validators<'_> {
+        Validators {
+            etag: self.etag.as_ref(),
+            last_modified: self.last_modified,
+        }
+    }
+}
+
+fn validators(res: &Response) -> OwnedValidators {
+    let header = |name| {
+        res.headers()
+            .get(name)
+            .and_then(|value: &HeaderValue| value.to_str().ok())
+    };
+
+    OwnedValidators {
+        etag: header(header::ETAG).and_then(|etag| etag.parse().ok()),
+        last_modified: header(header::LAST_MODIFIED)
+            .and_then(|date| httpdate::parse_http_date(date).ok()),
+    }
+}
+
+fn not_modified(res: Response) -> Response {
+    let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+    for name in NOT_MODIFIED_HEADERS {
+        for value in res.headers().get_all(&name) {
+            not_modified.headers_mut().append(&name, value.clone());
+        }
+    }
+    not_modified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preconditions(method: Method, headers: &[(HeaderName, &str)]) -> Preconditions {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(name, value.parse().unwrap());
+        }
+        Preconditions::from_headers(method, &map)
+    }
+
+    #[test]
+    fn parses_entity_tags() {
+        assert_eq!("\"a,b\"".parse(), Ok(ETag::strong("a,b")));
+        assert_eq!("W/\"v1\"".parse(), Ok(ETag::weak("v1")));
+        assert_eq!("v1".parse::<ETag>(), Err(InvalidETag));
+        assert_eq!(ETag::weak("v1").to_string(), "W/\"v1\"");
+    }
+
+    #[test]
+    fn evaluates_preconditions() {
+        let v1 = ETag::strong("v1");
+        let weak_v1 = ETag::weak("v1");
+
+        let get = preconditions(Method::GET, &[(header::IF_NONE_MATCH, "\"v0\", W/\"v1\"")]);
+        assert_eq!(
+            get.check(Some(&v1), None).unwrap_err().status(),
+            StatusCode::NOT_MODIFIED
+        );
+        assert!(get.check(Some(&ETag::strong("v2")), None).is_ok());
+
+        let put = preconditions(Method::PUT, &[(header::IF_MATCH, "\"v1\"")]);
+        assert!(put.check(Some(&v1), None).is_ok());
+        assert_eq!(
+            put.check(Some(&weak_v1), None).unwrap_err().status(),
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert!(put.check_absent().is_err());
+
+        let create = preconditions(Method::PUT, &[(header::IF_NONE_MATCH, "*")]);
+        assert!(create.check_absent().is_ok());
+        assert_eq!(
+            create.check(Some(&v1), None).unwrap_err().status(),
+            StatusCode::PRECONDITION_FAILED
+        );
+    }
+
+    #[test]
+    fn evaluates_dates() {
+        let modified = UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        let date = httpdate::fmt_http_date(modified);
+
+        let get = preconditions(Method::GET, &[(header::IF_MODIFIED_SINCE, date.as_str())]);
+        assert!(get.check(None, Some(modified)).unwrap_err().is_not_modified());
+        assert!(get
+            .check(None, Some(modified + std::time::Duration::from_secs(1)))
+            .is_ok());
+
+        let delete = preconditions(Method::DELETE, &[(header::IF_UNMODIFIED_SINCE, date.as_str())]);
+        assert!(delete.check(None, Some(modified)).is_ok());
+        assert!(delete
+            .check(None, Some(modified + std::time::Duration::from_secs(1)))
+            .is_err());
+    }
+
+    mod middleware {
+        use super::*;
+        use crate::{
+            body::Body,
+            routing::{get, put},
+            Router,
+        };
+        use tower::ServiceExt;
+
+        fn app() -> Router {
+            async fn document() -> (ETag, &'static str) {
+                (ETag::strong("v1"), "document")
+            }
+
+            Router::new()
+                .route("/document", get(document))
+                .route("/upload", put(|| async { ETag::strong("v2") }))
+                .layer(ConditionalRequestLayer::new())
+        }
+
+        async fn send(method: Method, uri: &str, (name, value): (HeaderName, &str)) -> Response {
+            let req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(name, value)
+                .body(Body::empty())
+                .unwrap();
+            app().oneshot(req).await.unwrap()
+        }
+
+        #[tokio::test]
+        async fn responds_not_modified() {
+            let res = send(Method::GET, "/document", (header::IF_NONE_MATCH, "\"v1\"")).await;
+            assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(res.headers()[header::ETAG], "\"v1\"");
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert!(body.is_empty());
+
+            let res = send(Method::GET, "/document", (header::IF_NONE_MATCH, "\"v0\"")).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
+        #[tokio::test]
+        async fn failed_if_match() {
+            let res = send(Method::GET, "/document", (header::IF_MATCH, "\"v0\"")).await;
+            assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        }
+
+        #[tokio::test]
+        async fn unsafe_methods_reach_the_handler() {
+            let res = send(Method::PUT, "/upload", (header::IF_MATCH, "\"v1\"")).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.headers()[header::ETAG], "\"v2\"");
+        }
+    }
+}
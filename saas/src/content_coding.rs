@@ -0,0 +1,194 @@
+//! Content codings supported by the compression and decompression layers.
+
+#[cfg(feature = "compression")]
+use http::{header, HeaderMap};
+#[cfg(feature = "decompression")]
+use http::HeaderValue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Gzip,
+    Deflate,
+    Br,
+    Zstd,
+}
+
+impl Encoding {
+    // in order of preference when the client accepts several with the same weight
+    #[cfg(feature = "compression")]
+    const PREFERENCE: [Self; 4] = [Self::Br, Self::Zstd, Self::Gzip, Self::Deflate];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+            Self::Br => "br",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    fn from_token(token: &str) -> Option<Self> {
+        if token.eq_ignore_ascii_case("gzip") || token.eq_ignore_ascii_case("x-gzip") {
+            Some(Self::Gzip)
+        } else if token.eq_ignore_ascii_case("deflate") {
+            Some(Self::Deflate)
+        } else if token.eq_ignore_ascii_case("br") {
+            Some(Self::Br)
+        } else if token.eq_ignore_ascii_case("zstd") {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Encodings {
+    pub(crate) gzip: bool,
+    pub(crate) deflate: bool,
+    pub(crate) br: bool,
+    pub(crate) zstd: bool,
+}
+
+impl Encodings {
+    pub(crate) const ALL: Self = Self {
+        gzip: true,
+        deflate: true,
+        br: true,
+        zstd: true,
+    };
+
+    #[cfg(feature = "compression")]
+    pub(crate) const NONE: Self = Self {
+        gzip: false,
+        deflate: false,
+        br: false,
+        zstd: false,
+    };
+
+    #[cfg(feature = "compression")]
+    pub(crate) fn is_empty(&self) -> bool {
+        !(self.gzip || self.deflate || self.br || self.zstd)
+    }
+
+    fn is_enabled(&self, encoding: Encoding) -> bool {
+        match encoding {
+            Encoding::Gzip => self.gzip,
+            Encoding::Deflate => self.deflate,
+            Encoding::Br => self.br,
+            Encoding::Zstd => self.zstd,
+        }
+    }
+
+    /// The enabled encoding named by a `Content-Encoding` token.
+    #[cfg(feature = "decompression")]
+    pub(crate) fn parse(&self, token: &str) -> Option<Encoding> {
+        Encoding::from_token(token).filter(|encoding| self.is_enabled(*encoding))
+    }
+
+    /// The enabled encodings, followed by `identity`, for an `Accept-Encoding` header.
+    #[cfg(feature = "decompression")]
+    pub(crate) fn accept_encoding(&self) -> HeaderValue {
+        let value = [Encoding::Gzip, Encoding::Deflate, Encoding::Br, Encoding::Zstd]
+            .into_iter()
+            .filter(|encoding| self.is_enabled(*encoding))
+            .map(Encoding::as_str)
+            .chain(Some("identity"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        HeaderValue::from_str(&value).expect("encoding names are valid header values")
+    }
+
+    /// The enabled encoding with the highest weight in the `Accept-Encoding` headers.
+    #[cfg(feature = "compression")]
+    pub(crate) fn negotiate(&self, headers: &HeaderMap) -> Option<Encoding> {
+        let mut weights = [None; 4];
+        let mut wildcard = None;
+
+        for value in headers.get_all(header::ACCEPT_ENCODING) {
+            let value = match value.to_str() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            for item in value.split(',') {
+                let mut params = item.split(';').map(str::trim);
+                let token = params.next().unwrap_or_default();
+                let weight = params
+                    .find_map(|param| {
+                        param
+                            .strip_prefix("q=")
+                            .or_else(|| param.strip_prefix("Q="))
+                    })
+                    .map_or(Some(1000), parse_weight);
+                let weight = match weight {
+                    Some(weight) => weight,
+                    None => continue,
+                };
+
+                if token == "*" {
+                    wildcard = Some(weight);
+                } else if let Some(encoding) = Encoding::from_token(token) {
+                    let index = Encoding::PREFERENCE
+                        .iter()
+                        .position(|preferred| *preferred == encoding)
+                        .expect("all encodings have a preference");
+                    weights[index] = Some(weight);
+                }
+            }
+        }
+
+        Encoding::PREFERENCE
+            .into_iter()
+            .zip(weights)
+            .filter(|(encoding, _)| self.is_enabled(*encoding))
+            .filter_map(|(encoding, weight)| Some((encoding, weight.or(wildcard)?)))
+            .filter(|(_, weight)| *weight > 0)
+            // `max_by_key` returns the last maximum, reverse to keep the preference order
+            .rev()
+            .max_by_key(|(_, weight)| *weight)
+            .map(|(encoding, _)| encoding)
+    }
+}
+
+// A qvalue in thousandths.
+#[cfg(feature = "compression")]
+fn parse_weight(value: &str) -> Option<u16> {
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+    if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let frac = format!("{:0<3}", frac).parse::<u16>().ok()?;
+    match int {
+        "0" => Some(frac),
+        "1" if frac == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn negotiate(encodings: Encodings, accept_encoding: &'static str) -> Option<Encoding> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT_ENCODING,
+            HeaderValue::from_static(accept_encoding),
+        );
+        encodings.negotiate(&headers)
+    }
+
+    #[test]
+    fn negotiates_by_weight_then_preference() {
+        let all = Encodings::ALL;
+        assert_eq!(negotiate(all, "gzip, deflate, br"), Some(Encoding::Br));
+        assert_eq!(negotiate(all, "gzip;q=1.0, br;q=0.5"), Some(Encoding::Gzip));
+        assert_eq!(negotiate(all, "br;q=0, *"), Some(Encoding::Zstd));
+        assert_eq!(negotiate(all, "identity"), None);
+        assert_eq!(negotiate(all, "gzip;q=0"), None);
+
+        let no_br = Encodings { br: false, ..all };
+        assert_eq!(negotiate(no_br, "br, gzip;q=0.8"), Some(Encoding::Gzip));
+    }
+}
@@ -0,0 +1,301 @@
+//! Decompression of request bodies.
+//!
+//! [`RequestDecompressionLayer`] decodes request bodies sent with a `Content-Encoding` of
+//! `gzip`, `deflate`, `br` or `zstd`, so extractors such as [`Json`](crate::Json),
+//! [`Form`](crate::extract::Form) and [`Bytes`](crate::body::Bytes) see the original body:
+//!
+//! ```rust,no_run
+//! use saas::{decompression::RequestDecompressionLayer, routing::post, Json, Router};
+//! use serde_json::Value;
+//!
+//! async fn create_user(Json(payload): Json<Value>) {}
+//!
+//! let app = Router::new()
+//!     .route("/users", post(create_user))
+//!     .layer(RequestDecompressionLayer::new());
+//! # let _: Router = app;
+//! ```
+//!
+//! The body is decoded while it is read, so [`DefaultBodyLimit`](crate::extract::DefaultBodyLimit)
+//! applies to the decompressed size and a small body that inflates to a huge one is rejected
+//! with `413 Payload Too Large` once the limit is reached.
+//!
+//! Requests with an encoding that isn't enabled are rejected with
+//! [`UnsupportedContentEncoding`], a `415 Unsupported Media Type` response with an
+//! `Accept-Encoding` header listing the enabled encodings.
+
+use crate::{
+    content_coding::{Encoding, Encodings},
+    extract::rejection::UnsupportedContentEncoding,
+};
+use async_compression::tokio::bufread::{BrotliDecoder, GzipDecoder, ZlibDecoder, ZstdDecoder};
+use futures_util::TryStreamExt;
+use http::{header, Request};
+use pin_project_lite::pin_project;
+use saas_core::{
+    body::Body,
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use std::{
+    fmt,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio_util::io::{ReaderStream, StreamReader};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer that decompresses request bodies.
+///
+/// All encodings are enabled by default. See the [module docs](self) for more details.
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub struct RequestDecompressionLayer {
+    encodings: Encodings,
+}
+
+impl RequestDecompressionLayer {
+    /// Create a new `RequestDecompressionLayer` with all encodings enabled.
+    pub fn new() -> Self {
+        Self {
+            encodings: Encodings::ALL,
+        }
+    }
+
+    /// Enable or disable `gzip`.
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.encodings.gzip = enable;
+        self
+    }
+
+    /// Enable or disable `deflate`.
+    pub fn deflate(mut self, enable: bool) -> Self {
+        self.encodings.deflate = enable;
+        self
+    }
+
+    /// Enable or disable `br`.
+    pub fn br(mut self, enable: bool) -> Self {
+        self.encodings.br = enable;
+        self
+    }
+
+    /// Enable or disable `zstd`.
+    pub fn zstd(mut self, enable: bool) -> Self {
+        self.encodings.zstd = enable;
+        self
+    }
+}
+
+impl Default for RequestDecompressionLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for RequestDecompressionLayer {
+    type Service = RequestDecompression<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestDecompression {
+            inner,
+            encodings: self.encodings,
+        }
+    }
+}
+
+/// Middleware that decompresses request bodies.
+///
+/// Created with [`RequestDecompressionLayer`].
+#[derive(Debug, Clone)]
+pub struct RequestDecompression<S> {
+    inner: S,
+    encodings: Encodings,
+}
+
+impl<B, S> Service<Request<B>> for RequestDecompression<S>
+where
+    B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+    S: Service<Request<Body>, Response = Response>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let req = req.map(Body::new);
+        let kind = match decompress(req, self.encodings) {
+            Ok(req) => Kind::Inner {
+                future: self.inner.call(req),
+            },
+            Err(res) => Kind::Unsupported { res: Some(res) },
+        };
+        ResponseFuture { kind }
+    }
+}
+
+pin_project! {
+    /// Response future for [`RequestDecompression`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        kind: Kind<F>,
+    }
+}
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<F> {
+        Inner {
+            #[pin]
+            future: F,
+        },
+        Unsupported {
+            res: Option<Response>,
+        },
+    }
+}
+
+impl<F> fmt::Debug for ResponseFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish_non_exhaustive()
+    }
+}
+
+impl<F, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response, E>>,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().kind.project() {
+            KindProj::Inner { future } => future.poll(cx),
+            KindProj::Unsupported { res } => {
+                Poll::Ready(Ok(res.take().expect("future polled after completion")))
+            }
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn decompress(mut req: Request<Body>, encodings: Encodings) -> Result<Request<Body>, Response> {
+    let mut decoders = Vec::new();
+    for value in req.headers().get_all(header::CONTENT_ENCODING) {
+        let value = value.to_str().map_err(|_| unsupported(encodings, "<invalid>"))?;
+        for token in value.split(',').map(str::trim).filter(|token| !token.is_empty()) {
+            if token.eq_ignore_ascii_case("identity") {
+                continue;
+            }
+            match encodings.parse(token) {
+                Some(encoding) => decoders.push(encoding),
+                None => return Err(unsupported(encodings, token)),
+            }
+        }
+    }
+
+    if req.headers().contains_key(header::CONTENT_ENCODING) {
+        let headers = req.headers_mut();
+        headers.remove(header::CONTENT_ENCODING);
+        if !decoders.is_empty() {
+            // the length is the length of the encoded body
+            headers.remove(header::CONTENT_LENGTH);
+        }
+    }
+
+    // encodings are listed in the order they were applied
+    for encoding in decoders.into_iter().rev() {
+        req = req.map(|body| decode(body, encoding));
+    }
+    Ok(req)
+}
+
+fn decode(body: Body, encoding: Encoding) -> Body {
+    let reader = StreamReader::new(body.map_err(|err| io::Error::new(io::ErrorKind::Other, err)));
+    match encoding {
+        Encoding::Gzip => {
+            let mut decoder = GzipDecoder::new(reader);
+            decoder.multiple_members(true);
+            Body::from_stream(ReaderStream::new(decoder))
+        }
+        Encoding::Deflate => Body::from_stream(ReaderStream::new(ZlibDecoder::new(reader))),
+        Encoding::Br => Body::from_stream(ReaderStream::new(BrotliDecoder::new(reader))),
+        Encoding::Zstd => Body::from_stream(ReaderStream::new(ZstdDecoder::new(reader))),
+    }
+}
+
+fn unsupported(encodings: Encodings, encoding: &str) -> Response {
+    let mut res = UnsupportedContentEncoding::from_err(format!("`{}`", encoding)).into_response();
+    res.headers_mut()
+        .insert(header::ACCEPT_ENCODING, encodings.accept_encoding());
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_compression::tokio::write::GzipEncoder;
+    use http::StatusCode;
+    use tokio::io::AsyncWriteExt;
+
+    async fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzipEncoder::new(Vec::new());
+        encoder.write_all(data).await.unwrap();
+        encoder.shutdown().await.unwrap();
+        encoder.into_inner()
+    }
+
+    #[tokio::test]
+    async fn decodes_body() {
+        let req = Request::post("/")
+            .header(header::CONTENT_ENCODING, "gzip")
+            .header(header::CONTENT_LENGTH, "42")
+            .body(Body::from(gzip(b"hello world").await))
+            .unwrap();
+
+        let req = decompress(req, Encodings::ALL).unwrap();
+        assert!(req.headers().get(header::CONTENT_ENCODING).is_none());
+        assert!(req.headers().get(header::CONTENT_LENGTH).is_none());
+
+        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        assert_eq!(body, "hello world");
+    }
+
+    #[test]
+    fn rejects_unsupported_encoding() {
+        let req = Request::post("/")
+            .header(header::CONTENT_ENCODING, "compress")
+            .body(Body::empty())
+            .unwrap();
+
+        let res = decompress(req, RequestDecompressionLayer::new().br(false).encodings).unwrap_err();
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(
+            res.headers()[header::ACCEPT_ENCODING],
+            "gzip, deflate, zstd, identity"
+        );
+    }
+
+    #[tokio::test]
+    async fn limit_applies_to_decompressed_size() {
+        use crate::extract::{DefaultBodyLimit, FromRequest};
+
+        let mut req = Request::post("/")
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(Body::from(gzip(&[0; 1024]).await))
+            .unwrap();
+        DefaultBodyLimit::max(512).__insert_into(req.extensions_mut());
+
+        let req = decompress(req, Encodings::ALL).unwrap();
+        let rejection = bytes::Bytes::from_request(req, &()).await.unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,448 @@
+use crate::{
+    extension::clone_extensions,
+    extract::{
+        rejection::{AnyRejection, BytesRejection},
+        DefaultBodyLimit, FromRequest, FromRequestParts, Request,
+    },
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{header::CONTENT_LENGTH, request::Parts};
+use saas_core::{
+    body::Body,
+    response::{IntoResponse, Response},
+};
+use std::fmt;
+
+/// Combines two extractors or responses into a single type.
+///
+/// # As extractor
+///
+/// The alternatives are tried in order and the first one that succeeds is used. If all of them
+/// fail the request is rejected with [`EitherRejection`], which responds with the status of the
+/// last alternative's rejection and the messages of all of them.
+///
+/// `Either` can be used with extractors that implement [`FromRequestParts`], in which case it
+/// can be used in any argument position:
+///
+/// ```rust,no_run
+/// use saas::{routing::get, Either, Extension, Router};
+///
+/// #[derive(Clone)]
+/// struct ApiKey(String);
+///
+/// #[derive(Clone)]
+/// struct Session(u64);
+///
+/// async fn handler(auth: Either<Extension<ApiKey>, Extension<Session>>) {
+///     match auth {
+///         Either::E1(Extension(api_key)) => { /* ... */ }
+///         Either::E2(Extension(session)) => { /* ... */ }
+///     }
+/// }
+///
+/// let app = Router::new().route("/", get(handler));
+/// # let _: Router = app;
+/// ```
+///
+/// Or with extractors that implement [`FromRequest`], in which case it must be the last
+/// argument:
+///
+/// ```rust,no_run
+/// use saas::{extract::Form, routing::post, Either, Json, Router};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct CreateUser {
+///     name: String,
+/// }
+///
+/// async fn create_user(payload: Either<Json<CreateUser>, Form<CreateUser>>) {
+///     let payload = match payload {
+///         Either::E1(Json(payload)) => payload,
+///         Either::E2(Form(payload)) => payload,
+///     };
+///     // ...
+/// }
+///
+/// let app = Router::new().route("/users", post(create_user));
+/// # let _: Router = app;
+/// ```
+///
+/// In the second case the body is buffered up front, respecting
+/// [`DefaultBodyLimit`], and every alternative gets a copy of the request with the buffered
+/// body. Request extensions can't be cloned in general, so the copies only have the extensions
+/// added by this crate: the path parameters, the matched path, `ConnectInfo`, and the values
+/// of [`Extension`] layers and config layers such as [`JsonConfig`](crate::json::JsonConfig).
+/// Extensions inserted in other ways, for example by third-party middleware, are only passed to
+/// the last alternative.
+///
+/// [`Extension`]: crate::Extension
+///
+/// # As response
+///
+/// ```rust
+/// use saas::{http::StatusCode, response::Redirect, Either};
+///
+/// async fn handler(logged_in: bool) -> Either<&'static str, Redirect> {
+///     if logged_in {
+///         Either::E1("Welcome back!")
+///     } else {
+///         Either::E2(Redirect::to("/login"))
+///     }
+/// }
+/// ```
+///
+/// See [`Either3`] and [`Either4`] for more alternatives.
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub enum Either<E1, E2> {
+    #[allow(missing_docs)]
+    E1(E1),
+    #[allow(missing_docs)]
+    E2(E2),
+}
+
+/// Combines three extractors or responses into a single type.
+///
+/// See [`Either`] for more details.
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub enum Either3<E1, E2, E3> {
+    #[allow(missing_docs)]
+    E1(E1),
+    #[allow(missing_docs)]
+    E2(E2),
+    #[allow(missing_docs)]
+    E3(E3),
+}
+
+/// Combines four extractors or responses into a single type.
+///
+/// See [`Either`] for more details.
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub enum Either4<E1, E2, E3, E4> {
+    #[allow(missing_docs)]
+    E1(E1),
+    #[allow(missing_docs)]
+    E2(E2),
+    #[allow(missing_docs)]
+    E3(E3),
+    #[allow(missing_docs)]
+    E4(E4),
+}
+
+macro_rules! impl_either {
+    ( $either:ident, [$($ty:ident),*], $last:ident ) => {
+        #[async_trait]
+        impl<S, $($ty,)* $last> FromRequestParts<S> for $either<$($ty,)* $last>
+        where
+            S: Send + Sync,
+            $( $ty: FromRequestParts<S>, $ty::Rejection: Send, )*
+            $last: FromRequestParts<S>,
+        {
+            type Rejection = EitherRejection<($($ty::Rejection,)* $last::Rejection,)>;
+
+            #[allow(non_snake_case)]
+            async fn from_request_parts(
+                parts: &mut Parts,
+                state: &S,
+            ) -> Result<Self, Self::Rejection> {
+                $(
+                    let $ty = match $ty::from_request_parts(parts, state).await {
+                        Ok(value) => return Ok(Self::$ty(value)),
+                        Err(rejection) => rejection,
+                    };
+                )*
+                let $last = match $last::from_request_parts(parts, state).await {
+                    Ok(value) => return Ok(Self::$last(value)),
+                    Err(rejection) => rejection,
+                };
+                Err(EitherRejection::Alternatives(($($ty,)* $last,)))
+            }
+        }
+
+        #[async_trait]
+        impl<S, $($ty,)* $last> FromRequest<S> for $either<$($ty,)* $last>
+        where
+            S: Send + Sync,
+            $( $ty: FromRequest<S>, $ty::Rejection: Send, )*
+            $last: FromRequest<S>,
+        {
+            type Rejection = EitherRejection<($($ty::Rejection,)* $last::Rejection,)>;
+
+            #[allow(non_snake_case)]
+            async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+                let (parts, body) = req.into_parts();
+                let limit = DefaultBodyLimit::__resolve(&parts.extensions, &parts.headers);
+                let bytes = Bytes::from_request(replay(&parts, body, limit), state)
+                    .await
+                    .map_err(EitherRejection::Body)?;
+
+                $(
+                    let req = replay(&parts, Body::from(bytes.clone()), limit);
+                    let $ty = match $ty::from_request(req, state).await {
+                        Ok(value) => return Ok(Self::$ty(value)),
+                        Err(rejection) => rejection,
+                    };
+                )*
+                let req = Request::from_parts(parts, Body::from(bytes));
+                let $last = match $last::from_request(req, state).await {
+                    Ok(value) => return Ok(Self::$last(value)),
+                    Err(rejection) => rejection,
+                };
+                Err(EitherRejection::Alternatives(($($ty,)* $last,)))
+            }
+        }
+
+        impl<$($ty,)* $last> IntoResponse for $either<$($ty,)* $last>
+        where
+            $( $ty: IntoResponse, )*
+            $last: IntoResponse,
+        {
+            fn into_response(self) -> Response {
+                match self {
+                    $( Self::$ty(value) => value.into_response(), )*
+                    Self::$last(value) => value.into_response(),
+                }
+            }
+        }
+
+        impl<$($ty,)* $last> IntoResponse for EitherRejection<($($ty,)* $last,)>
+        where
+            $( $ty: IntoResponse, )*
+            $last: IntoResponse,
+        {
+            #[allow(non_snake_case)]
+            fn into_response(self) -> Response {
+                match self {
+                    Self::Body(rejection) => rejection.into_response(),
+                    Self::Alternatives(($($ty,)* $last,)) => combine_rejections(vec![
+                        $( $ty.into_response(), )*
+                        $last.into_response(),
+                    ]),
+                }
+            }
+        }
+
+        impl<$($ty,)* $last> fmt::Display for EitherRejection<($($ty,)* $last,)>
+        where
+            $( $ty: fmt::Display, )*
+            $last: fmt::Display,
+        {
+            #[allow(non_snake_case)]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    Self::Body(rejection) => write!(f, "{}", rejection),
+                    Self::Alternatives(($($ty,)* $last,)) => {
+                        $( write!(f, "{}; ", $ty)?; )*
+                        write!(f, "{}", $last)
+                    }
+                }
+            }
+        }
+
+        impl<$($ty,)* $last> std::error::Error for EitherRejection<($($ty,)* $last,)>
+        where
+            $( $ty: std::error::Error + 'static, )*
+            $last: std::error::Error + 'static,
+        {
+            #[allow(non_snake_case)]
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    Self::Body(rejection) => Some(rejection),
+                    Self::Alternatives((.., $last)) => Some($last),
+                }
+            }
+        }
+    };
+}
+
+impl_either!(Either, [E1], E2);
+impl_either!(Either3, [E1, E2], E3);
+impl_either!(Either4, [E1, E2, E3], E4);
+
+/// Build a request with the same head as `parts` and the extensions that can be cloned.
+///
+/// `limit` is the body limit resolved for the original request, which isn't one of them.
+fn replay(parts: &Parts, body: Body, limit: Option<usize>) -> Request {
+    let mut req = Request::new(body);
+    *req.method_mut() = parts.method.clone();
+    *req.uri_mut() = parts.uri.clone();
+    *req.version_mut() = parts.version;
+    *req.headers_mut() = parts.headers.clone();
+    *req.extensions_mut() = clone_extensions(&parts.extensions);
+    let limit = match limit {
+        Some(limit) => DefaultBodyLimit::max(limit),
+        None => DefaultBodyLimit::disable(),
+    };
+    limit.__insert_into(req.extensions_mut());
+    req
+}
+
+/// Respond with the last rejection, with the messages of all of them as the body.
+///
+/// Rejections that aren't built-in have an unknown body format, if there are any the last
+/// response is used as is.
+fn combine_rejections(mut responses: Vec<Response>) -> Response {
+    let messages = responses
+        .iter()
+        .map(|res| {
+            res.extensions()
+                .get::<AnyRejection>()
+                .map(|rejection| rejection.body_text().to_owned())
+        })
+        .collect::<Option<Vec<_>>>();
+    let mut res = responses.pop().expect("there are always alternatives");
+
+    if let Some(messages) = messages {
+        res.headers_mut().remove(CONTENT_LENGTH);
+        *res.body_mut() = Body::from(messages.join("; "));
+    }
+    res
+}
+
+/// Rejection used for [`Either`], [`Either3`] and [`Either4`].
+#[derive(Debug)]
+pub enum EitherRejection<R> {
+    /// Buffering the request body failed.
+    ///
+    /// Only used when extracting the alternatives with [`FromRequest`].
+    Body(BytesRejection),
+    /// Every alternative was rejected.
+    ///
+    /// Contains the rejection of each alternative, in order. Responds with the status of the last
+    /// alternative's rejection.
+    Alternatives(R),
+}
+
+#[cfg(all(test, feature = "form", feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::{extract::rejection::JsonRejection, form::Form, Json};
+    use http::header::CONTENT_TYPE;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Payload {
+        name: String,
+    }
+
+    #[tokio::test]
+    async fn tries_alternatives_in_order() {
+        let req = http::Request::post("/")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from("name=one"))
+            .unwrap();
+        let extracted = Either::<Json<Payload>, Form<Payload>>::from_request(req, &())
+            .await
+            .unwrap();
+        assert!(matches!(extracted, Either::E2(Form(payload)) if payload.name == "one"));
+
+        let req = http::Request::post("/")
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Body::from("name=one"))
+            .unwrap();
+        let rejection = Either::<Json<Payload>, Json<Payload>>::from_request(req, &())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            rejection,
+            EitherRejection::Alternatives((
+                JsonRejection::MissingJsonContentType(_),
+                JsonRejection::MissingJsonContentType(_),
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn responds_with_every_rejection() {
+        let req = http::Request::post("/")
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Body::from("name=one"))
+            .unwrap();
+        let res = Either::<Json<Payload>, Form<Payload>>::from_request(req, &())
+            .await
+            .unwrap_err()
+            .into_response();
+        assert_eq!(res.status(), http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(
+            body,
+            "Expected request with `Content-Type: application/json`; \
+             Form requests must have `Content-Type: application/x-www-form-urlencoded`"
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_the_body_limit() {
+        use crate::{routing::post, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/",
+                post(|_: Either<Json<Payload>, Form<Payload>>| async {}),
+            )
+            .layer(DefaultBodyLimit::max(8));
+
+        let req = http::Request::post("/")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"name":"too long"}"#))
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::PAYLOAD_TOO_LARGE);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(
+            body,
+            "Request body is too large: length limit of 8 bytes exceeded"
+        );
+    }
+
+    #[tokio::test]
+    async fn alternatives_get_path_params_and_extensions() {
+        use crate::{extract::Path, routing::post, Extension, Router};
+        use tower::ServiceExt;
+
+        #[derive(Debug)]
+        struct WithParams(u32, &'static str);
+
+        #[async_trait]
+        impl<S> FromRequest<S> for WithParams
+        where
+            S: Send + Sync,
+        {
+            type Rejection = Response;
+
+            async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+                let (mut parts, _) = req.into_parts();
+                let Path(id) = Path::<u32>::from_request_parts(&mut parts, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+                let Extension(name) =
+                    Extension::<&'static str>::from_request_parts(&mut parts, state)
+                        .await
+                        .map_err(IntoResponse::into_response)?;
+                Ok(Self(id, name))
+            }
+        }
+
+        let app = Router::new()
+            .route(
+                "/users/:id",
+                post(|extracted: Either<WithParams, Bytes>| async move {
+                    match extracted {
+                        Either::E1(WithParams(id, name)) => format!("{} {}", id, name),
+                        Either::E2(_) => "fallback".to_owned(),
+                    }
+                }),
+            )
+            .layer(Extension("one"));
+
+        let req = http::Request::post("/users/1").body(Body::empty()).unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "1 one");
+    }
+}
@@ -1,14 +1,15 @@
 use std::convert::Infallible;
 
 use saas_core::{extract::{FromRequestParts, Request}, response::{IntoResponseParts, ResponseParts, IntoResponse, Response}};
-use http::request::Parts;
+use http::{request::Parts, Extensions};
+use std::any::TypeId;
 use async_trait::async_trait;
 use tower_layer::Layer;
 use tower_service::Service;
 
 use crate::extract::rejection::{ExtensionRejection, MissingExtension};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 #[must_use]
 pub struct Extension<T>(pub T);
 
@@ -96,7 +97,54 @@ where
     }
 
     fn call(&mut self, mut req: Request<ResBody>) -> Self::Future {
-        req.extensions_mut().insert(self.value.clone());
+        insert_cloneable(req.extensions_mut(), self.value.clone());
         self.inner.call(req)
     }
+}
+
+// Request extension recording which of the other extensions can be cloned. `Extensions` itself
+// can't be cloned, but extractors that consume the request, such as the alternatives of
+// `Either`, still need the extensions added by `Extension` and the router.
+#[derive(Clone)]
+struct CloneableExtensions(Vec<(TypeId, fn(&Extensions, &mut Extensions))>);
+
+/// Insert `value` and record that it can be copied by [`clone_extensions`].
+pub(crate) fn insert_cloneable<T>(extensions: &mut Extensions, value: T)
+where
+    T: Clone + Send + Sync + 'static,
+{
+    extensions.insert(value);
+
+    let entry = (TypeId::of::<T>(), clone_extension::<T> as fn(&Extensions, &mut Extensions));
+    match extensions.get_mut::<CloneableExtensions>() {
+        Some(CloneableExtensions(cloneable)) => {
+            if !cloneable.iter().any(|(type_id, _)| *type_id == entry.0) {
+                cloneable.push(entry);
+            }
+        }
+        None => {
+            extensions.insert(CloneableExtensions(vec![entry]));
+        }
+    }
+}
+
+fn clone_extension<T>(from: &Extensions, to: &mut Extensions)
+where
+    T: Clone + Send + Sync + 'static,
+{
+    if let Some(value) = from.get::<T>() {
+        to.insert(value.clone());
+    }
+}
+
+/// Copy the extensions inserted with [`insert_cloneable`].
+pub(crate) fn clone_extensions(extensions: &Extensions) -> Extensions {
+    let mut cloned = Extensions::new();
+    if let Some(cloneable) = extensions.get::<CloneableExtensions>() {
+        for (_, clone) in &cloneable.0 {
+            clone(extensions, &mut cloned);
+        }
+        cloned.insert(cloneable.clone());
+    }
+    cloned
 }
\ No newline at end of file
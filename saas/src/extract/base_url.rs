@@ -0,0 +1,254 @@
+use super::{
+    host::forwarded_param,
+    rejection::{BaseUrlRejection, HostRejection, InvalidHost},
+    FromRequestParts, Host,
+};
+use async_trait::async_trait;
+use http::{
+    request::Parts,
+    uri::{self, Authority, PathAndQuery, Uri},
+    HeaderName,
+};
+use std::{convert::Infallible, fmt};
+
+const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+
+/// Extractor that resolves the scheme the client used for the request.
+///
+/// The scheme is resolved from, in order, the `proto` parameter of `Forwarded`,
+/// `X-Forwarded-Proto`, the scheme of the request URI and [`TlsConnection`]. It defaults to
+/// `http`. The forwarding headers are subject to the same rules as for [`Host`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scheme(pub uri::Scheme);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Scheme
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(scheme) = forwarded_param(parts, "proto", &X_FORWARDED_PROTO)
+            .and_then(|proto| proto.parse().ok())
+        {
+            return Ok(Self(scheme));
+        }
+
+        if let Some(scheme) = parts.uri.scheme() {
+            return Ok(Self(scheme.clone()));
+        }
+
+        if parts.extensions.get::<TlsConnection>().is_some() {
+            return Ok(Self(uri::Scheme::HTTPS));
+        }
+
+        Ok(Self(uri::Scheme::HTTP))
+    }
+}
+
+saas_core::__impl_deref!(Scheme: uri::Scheme);
+
+/// Request extension that marks the connection as using TLS.
+///
+/// Servers that terminate TLS themselves should add it to every request, for example with
+/// [`Extension`](crate::Extension), so that [`Scheme`] and [`BaseUrl`] resolve to `https`
+/// without any forwarding headers.
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct TlsConnection;
+
+/// Extractor for the external base URL of the app, such as `https://example.com:8443`.
+///
+/// Combines [`Scheme`] and [`Host`]. The port is omitted if it's the default port of the
+/// scheme.
+///
+/// ```rust,no_run
+/// use saas::{
+///     extract::BaseUrl,
+///     http::{
+///         header::{HeaderName, LOCATION},
+///         StatusCode,
+///     },
+///     routing::post,
+///     Router,
+/// };
+///
+/// async fn create_user(base_url: BaseUrl) -> (StatusCode, [(HeaderName, String); 1]) {
+///     let location = base_url.join("/users/1").unwrap();
+///     (StatusCode::CREATED, [(LOCATION, location.to_string())])
+/// }
+///
+/// let app = Router::new().route("/users", post(create_user));
+/// # let _: Router = app;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseUrl {
+    scheme: uri::Scheme,
+    authority: Authority,
+}
+
+impl BaseUrl {
+    /// The scheme, such as `https`.
+    pub fn scheme(&self) -> &uri::Scheme {
+        &self.scheme
+    }
+
+    /// The host and, if it isn't the default port of the scheme, the port.
+    pub fn authority(&self) -> &Authority {
+        &self.authority
+    }
+
+    /// Build an absolute URL for `path_and_query`, such as `/users?page=2`.
+    pub fn join(&self, path_and_query: &str) -> Result<Uri, http::Error> {
+        Uri::builder()
+            .scheme(self.scheme.clone())
+            .authority(self.authority.clone())
+            .path_and_query(path_and_query)
+            .build()
+    }
+}
+
+impl fmt::Display for BaseUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.authority)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for BaseUrl
+where
+    S: Send + Sync,
+{
+    type Rejection = BaseUrlRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Scheme(scheme) = match Scheme::from_request_parts(parts, state).await {
+            Ok(scheme) => scheme,
+            Err(never) => match never {},
+        };
+        let Host(host) = Host::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| match rejection {
+                HostRejection::FailedToResolveHost(rejection) => rejection,
+            })?;
+
+        let authority = host.parse::<Authority>().map_err(InvalidHost::from_err)?;
+        let default_port = match scheme.as_str() {
+            "https" | "wss" => Some(443),
+            "http" | "ws" => Some(80),
+            _ => None,
+        };
+        let authority = if authority.port_u16().is_some() && authority.port_u16() == default_port {
+            authority
+                .host()
+                .parse()
+                .map_err(InvalidHost::from_err)?
+        } else {
+            authority
+        };
+
+        Ok(Self { scheme, authority })
+    }
+}
+
+/// Extractor for the absolute URL the client requested, such as
+/// `https://example.com/api/users?page=2`.
+///
+/// Combines [`BaseUrl`] with the path and query of
+/// [`OriginalUri`](super::OriginalUri), so the full URL is returned even inside
+/// [`Router::nest`](crate::Router::nest).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestUrl(pub Uri);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestUrl
+where
+    S: Send + Sync,
+{
+    type Rejection = BaseUrlRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let base_url = BaseUrl::from_request_parts(parts, state).await?;
+
+        #[cfg(feature = "original-uri")]
+        let uri = match super::OriginalUri::from_request_parts(parts, state).await {
+            Ok(super::OriginalUri(uri)) => uri,
+            Err(never) => match never {},
+        };
+        #[cfg(not(feature = "original-uri"))]
+        let uri = parts.uri.clone();
+
+        let path_and_query = uri
+            .path_and_query()
+            .map_or("/", PathAndQuery::as_str);
+        base_url
+            .join(path_and_query)
+            .map(Self)
+            .map_err(|err| InvalidHost::from_err(err).into())
+    }
+}
+
+saas_core::__impl_deref!(RequestUrl: Uri);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Request;
+
+    async fn base_url(req: Request<()>) -> String {
+        let (mut parts, _) = req.into_parts();
+        BaseUrl::from_request_parts(&mut parts, &())
+            .await
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn resolves_scheme_and_host() {
+        let req = Request::builder()
+            .header("host", "example.com:80")
+            .body(())
+            .unwrap();
+        assert_eq!(base_url(req).await, "http://example.com");
+
+        let req = Request::builder()
+            .header("host", "internal:8080")
+            .header("forwarded", "proto=https;host=example.com")
+            .body(())
+            .unwrap();
+        assert_eq!(base_url(req).await, "https://example.com");
+
+        let req = Request::builder()
+            .header("host", "example.com:8443")
+            .header("x-forwarded-proto", "https")
+            .body(())
+            .unwrap();
+        assert_eq!(base_url(req).await, "https://example.com:8443");
+    }
+
+    #[tokio::test]
+    async fn uses_the_scheme_of_the_trusted_proxy() {
+        use crate::extract::{
+            client_ip::{ForwardedHeader, TrustedProxies},
+            ConnectInfo,
+        };
+        use std::net::SocketAddr;
+
+        let mut req = Request::builder()
+            .header("host", "example.com")
+            .header("x-forwarded-for", "1.1.1.1")
+            .header("x-forwarded-proto", "https, http")
+            .body(())
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 1234))));
+        req.extensions_mut().insert(
+            TrustedProxies::new()
+                .trust("10.0.0.0/8".parse().unwrap())
+                .header(ForwardedHeader::XForwardedFor),
+        );
+        // `https` was sent by the client, the proxy appended `http`
+        assert_eq!(base_url(req).await, "http://example.com");
+    }
+}
@@ -0,0 +1,444 @@
+//! Extractor that streams the request body.
+//!
+//! See [`BodyStream`] for more details.
+
+use super::{
+    rejection::{AnyRejection, BodyLimitExceeded},
+    DefaultBodyLimit, FromRequest, Request,
+};
+use crate::body::Bytes;
+use async_trait::async_trait;
+use futures_util::stream::Stream;
+use http::StatusCode;
+use saas_core::{
+    body::Body,
+    response::{IntoResponse, Response},
+    RequestExt,
+};
+use std::{
+    convert::Infallible,
+    error::Error as _,
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "tokio")]
+use crate::Extension;
+#[cfg(feature = "tokio")]
+use std::{future::Future, time::Duration};
+#[cfg(feature = "tokio")]
+use tokio::time::{Instant, Sleep};
+#[cfg(feature = "tokio")]
+use tower_layer::Layer;
+
+/// Extractor that streams the request body as it arrives.
+///
+/// Unlike [`Bytes`] the body isn't buffered, chunks are yielded as they are received. The
+/// body is still limited by [`DefaultBodyLimit`](super::DefaultBodyLimit), going over the limit
+/// yields an error that responds with `413 Payload Too Large`.
+///
+/// Slow uploads can be cut off with [`BodyReadTimeout`], in which case the stream yields an error
+/// that responds with `408 Request Timeout`.
+///
+/// ```rust,no_run
+/// use saas::{extract::BodyStream, routing::post, Router};
+/// use futures_util::StreamExt;
+///
+/// async fn upload(mut stream: BodyStream) -> Result<(), saas::extract::body_stream::BodyStreamError> {
+///     while let Some(chunk) = stream.next().await {
+///         let chunk = chunk?;
+///         // ...
+///     }
+///     Ok(())
+/// }
+///
+/// let app = Router::new().route("/upload", post(upload));
+/// # let _: Router = app;
+/// ```
+pub struct BodyStream {
+    body: Body,
+    limit: Option<usize>,
+    #[cfg(feature = "tokio")]
+    deadline: Option<ReadDeadline>,
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyStream").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequest<S> for BodyStream
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        #[cfg(feature = "tokio")]
+        let deadline = req
+            .extensions()
+            .get::<BodyReadTimeout>()
+            .and_then(BodyReadTimeout::start);
+
+        let limit = DefaultBodyLimit::__resolve(req.extensions(), req.headers());
+        let body = match req.into_limited_body() {
+            Ok(limited) => Body::new(limited),
+            Err(unlimited) => unlimited,
+        };
+
+        Ok(Self {
+            body,
+            limit,
+            #[cfg(feature = "tokio")]
+            deadline,
+        })
+    }
+}
+
+impl Stream for BodyStream {
+    type Item = Result<Bytes, BodyStreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        #[cfg(feature = "tokio")]
+        if let Some(deadline) = &mut this.deadline {
+            if deadline.poll_expired(cx).is_ready() {
+                // stop reading, there is no point in yielding more data after the timeout
+                this.deadline = None;
+                this.body = Body::empty();
+                return Poll::Ready(Some(Err(BodyStreamError {
+                    kind: ErrorKind::Timeout,
+                })));
+            }
+        }
+
+        match futures_util::ready!(Pin::new(&mut this.body).poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                #[cfg(feature = "tokio")]
+                if let Some(deadline) = &mut this.deadline {
+                    deadline.received(chunk.len());
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Some(Err(err)) => Poll::Ready(Some(Err(BodyStreamError::from_body(err, this.limit)))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// Timeouts applied while reading a [`BodyStream`].
+///
+/// Used as a layer, the same way as [`DefaultBodyLimit`](super::DefaultBodyLimit).
+///
+/// ```rust
+/// use saas::{
+///     extract::{body_stream::BodyReadTimeout, BodyStream},
+///     routing::post,
+///     Router,
+/// };
+/// use std::time::Duration;
+///
+/// async fn upload(stream: BodyStream) {}
+///
+/// let app = Router::new().route(
+///     "/upload",
+///     post(upload).layer(
+///         BodyReadTimeout::new()
+///             .total(Duration::from_secs(60))
+///             // at least 16 KiB every 5 seconds
+///             .min_throughput(16 * 1024, Duration::from_secs(5)),
+///     ),
+/// );
+/// # let _: Router = app;
+/// ```
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+#[derive(Debug, Clone, Copy, Default)]
+#[must_use]
+pub struct BodyReadTimeout {
+    total: Option<Duration>,
+    min_throughput: Option<(u64, Duration)>,
+}
+
+#[cfg(feature = "tokio")]
+impl BodyReadTimeout {
+    /// Create a new `BodyReadTimeout` without any timeouts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum time reading the whole body may take.
+    pub fn total(mut self, timeout: Duration) -> Self {
+        self.total = Some(timeout);
+        self
+    }
+
+    /// Require at least `bytes` to be received in every `window`.
+    pub fn min_throughput(mut self, bytes: u64, window: Duration) -> Self {
+        self.min_throughput = Some((bytes, window));
+        self
+    }
+
+    fn start(&self) -> Option<ReadDeadline> {
+        if self.total.is_none() && self.min_throughput.is_none() {
+            return None;
+        }
+
+        Some(ReadDeadline {
+            total: self.total.map(|timeout| Box::pin(tokio::time::sleep(timeout))),
+            throughput: self.min_throughput.map(|(min_bytes, window)| Throughput {
+                min_bytes,
+                window,
+                received: 0,
+                sleep: Box::pin(tokio::time::sleep(window)),
+            }),
+        })
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<S> Layer<S> for BodyReadTimeout {
+    type Service = <Extension<Self> as Layer<S>>::Service;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Extension(*self).layer(inner)
+    }
+}
+
+#[cfg(feature = "tokio")]
+struct ReadDeadline {
+    total: Option<Pin<Box<Sleep>>>,
+    throughput: Option<Throughput>,
+}
+
+#[cfg(feature = "tokio")]
+struct Throughput {
+    min_bytes: u64,
+    window: Duration,
+    received: u64,
+    sleep: Pin<Box<Sleep>>,
+}
+
+#[cfg(feature = "tokio")]
+impl ReadDeadline {
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some(total) = &mut self.total {
+            if total.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(());
+            }
+        }
+
+        if let Some(throughput) = &mut self.throughput {
+            if throughput.sleep.as_mut().poll(cx).is_ready() {
+                if throughput.received < throughput.min_bytes {
+                    return Poll::Ready(());
+                }
+
+                throughput.received = 0;
+                let next = Instant::now() + throughput.window;
+                throughput.sleep.as_mut().reset(next);
+                // register the waker for the new window
+                let _ = throughput.sleep.as_mut().poll(cx);
+            }
+        }
+
+        Poll::Pending
+    }
+
+    fn received(&mut self, len: usize) {
+        if let Some(throughput) = &mut self.throughput {
+            throughput.received = throughput.received.saturating_add(len as u64);
+        }
+    }
+}
+
+/// Errors yielded by [`BodyStream`].
+#[derive(Debug)]
+pub struct BodyStreamError {
+    kind: ErrorKind,
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    LengthLimit(crate::Error),
+    #[cfg(feature = "tokio")]
+    Timeout,
+    Body(crate::Error),
+}
+
+impl BodyStreamError {
+    fn from_body(err: crate::Error, limit: Option<usize>) -> Self {
+        let is_length_limit = err
+            .source()
+            .and_then(|err| err.downcast_ref::<http_body::LengthLimitError>())
+            .is_some();
+
+        let kind = match (is_length_limit, limit) {
+            (true, Some(limit)) => {
+                ErrorKind::LengthLimit(crate::Error::new(BodyLimitExceeded::__new(limit)))
+            }
+            (true, None) => ErrorKind::LengthLimit(err),
+            (false, _) => ErrorKind::Body(err),
+        };
+        Self { kind }
+    }
+
+    /// Returns `true` if the stream was cut off by a [`BodyReadTimeout`].
+    pub fn is_timeout(&self) -> bool {
+        #[cfg(feature = "tokio")]
+        {
+            matches!(self.kind, ErrorKind::Timeout)
+        }
+        #[cfg(not(feature = "tokio"))]
+        {
+            false
+        }
+    }
+
+    /// Get the response body text used for this rejection.
+    pub fn body_text(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get the status code used for this rejection.
+    pub fn status(&self) -> StatusCode {
+        match self.kind {
+            ErrorKind::LengthLimit(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            #[cfg(feature = "tokio")]
+            ErrorKind::Timeout => StatusCode::REQUEST_TIMEOUT,
+            ErrorKind::Body(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl fmt::Display for BodyStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::LengthLimit(err) => write!(f, "Request body is too large: {}", err),
+            #[cfg(feature = "tokio")]
+            ErrorKind::Timeout => f.write_str("Timed out reading the request body"),
+            ErrorKind::Body(err) => write!(f, "Failed to read the request body: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BodyStreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::LengthLimit(err) | ErrorKind::Body(err) => Some(err),
+            #[cfg(feature = "tokio")]
+            ErrorKind::Timeout => None,
+        }
+    }
+}
+
+impl IntoResponse for BodyStreamError {
+    fn into_response(self) -> Response {
+        saas_core::__log_rejection!(
+            rejection_type = BodyStreamError,
+            body_text = self.body_text(),
+            status = self.status(),
+        );
+        let status = self.status();
+        let body_text = self.body_text();
+        let (name, title, detail) = match &self.kind {
+            ErrorKind::LengthLimit(err) => {
+                ("LengthLimitError", "Request body is too large", Some(err.to_string()))
+            }
+            #[cfg(feature = "tokio")]
+            ErrorKind::Timeout => ("BodyReadTimeout", "Timed out reading the request body", None),
+            ErrorKind::Body(err) => {
+                ("UnknowBodyError", "Failed to read the request body", Some(err.to_string()))
+            }
+        };
+        let mut res = (status, body_text.clone()).into_response();
+        res.extensions_mut().insert(
+            AnyRejection::__new(status, body_text, self).__with_message(name, title, detail),
+        );
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::DefaultBodyLimit;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn length_limit() {
+        let mut req = Request::new(Body::from(vec![0; 16]));
+        DefaultBodyLimit::max(8).__insert_into(req.extensions_mut());
+
+        let mut stream = BodyStream::from_request(req, &()).await.unwrap();
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(err.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            err.body_text(),
+            "Request body is too large: length limit of 8 bytes exceeded"
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn total_timeout() {
+        let body = Body::from_stream(futures_util::stream::pending::<Result<Bytes, Infallible>>());
+        let mut req = Request::new(body);
+        req.extensions_mut()
+            .insert(BodyReadTimeout::new().total(Duration::from_secs(1)));
+
+        let mut stream = BodyStream::from_request(req, &()).await.unwrap();
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(err.is_timeout());
+        assert_eq!(err.status(), StatusCode::REQUEST_TIMEOUT);
+        assert!(stream.next().await.is_none());
+    }
+
+    // a body that yields a chunk of `len` bytes every second, `count` times
+    #[cfg(feature = "tokio")]
+    fn trickle(len: usize, count: usize) -> Body {
+        Body::from_stream(futures_util::stream::iter(0..count).then(move |_| async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok::<_, Infallible>(Bytes::from(vec![0; len]))
+        }))
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn min_throughput_timeout() {
+        let mut req = Request::new(trickle(1, 100));
+        req.extensions_mut()
+            .insert(BodyReadTimeout::new().min_throughput(10, Duration::from_secs(2)));
+
+        let mut stream = BodyStream::from_request(req, &()).await.unwrap();
+        let mut received = 0;
+        let err = loop {
+            match stream.next().await.unwrap() {
+                Ok(chunk) => received += chunk.len(),
+                Err(err) => break err,
+            }
+        };
+        assert!(err.is_timeout());
+        assert!(received < 10);
+        assert!(stream.next().await.is_none());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn min_throughput_met() {
+        let mut req = Request::new(trickle(16, 5));
+        req.extensions_mut()
+            .insert(BodyReadTimeout::new().min_throughput(10, Duration::from_secs(2)));
+
+        let stream = BodyStream::from_request(req, &()).await.unwrap();
+        let chunks = stream.collect::<Vec<_>>().await;
+        assert_eq!(chunks.len(), 5);
+        for chunk in chunks {
+            assert_eq!(chunk.unwrap().len(), 16);
+        }
+    }
+}
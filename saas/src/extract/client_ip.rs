@@ -0,0 +1,468 @@
+//! Extractor for the IP address of the client, taking trusted proxies into account.
+//!
+//! See [`ClientIp`] for more details.
+
+use super::{
+    connect_info::{ConnectInfo, MockConnectInfo},
+    rejection::{ClientIpRejection, MissingConnectInfo},
+    FromRequestParts,
+};
+use crate::Extension;
+use async_trait::async_trait;
+use http::{
+    header::{HeaderName, FORWARDED},
+    request::Parts,
+    Extensions, HeaderMap,
+};
+use std::{
+    error::Error,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+use tower_layer::Layer;
+
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+const X_REAL_IP: HeaderName = HeaderName::from_static("x-real-ip");
+
+/// Extractor for the IP address of the client.
+///
+/// Without [`TrustedProxies`] this is the address of the peer, the same as
+/// [`ConnectInfo<SocketAddr>`](super::ConnectInfo). If the peer is a trusted proxy the
+/// [header](TrustedProxies::header) the proxies append to is walked from right to left and the
+/// first hop that isn't a trusted proxy is returned. If every hop is trusted the leftmost one
+/// is returned, if a hop can't be parsed the walk stops at the hop before it. Other forwarding
+/// headers are ignored, as the client can send them.
+///
+/// Requires the app to be served with
+/// [`Router::into_make_service_with_connect_info`](crate::Router::into_make_service_with_connect_info).
+///
+/// ```rust,no_run
+/// use saas::{
+///     extract::client_ip::{ClientIp, TrustedProxies},
+///     routing::get,
+///     Router,
+/// };
+/// use std::net::SocketAddr;
+///
+/// async fn handler(ClientIp(ip): ClientIp) -> String {
+///     ip.to_string()
+/// }
+///
+/// let app = Router::new().route("/", get(handler)).layer(
+///     TrustedProxies::new()
+///         .trust("10.0.0.0/8".parse().unwrap())
+///         .trust("127.0.0.1".parse().unwrap()),
+/// );
+///
+/// # async {
+/// let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
+/// saas::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+///     .await
+///     .unwrap();
+/// # };
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = ClientIpRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = peer_ip(&parts.extensions).ok_or(MissingConnectInfo)?;
+
+        let trusted = match parts.extensions.get::<TrustedProxies>() {
+            Some(trusted) if trusted.contains(peer) => trusted,
+            _ => return Ok(Self(peer)),
+        };
+
+        let hops = match trusted.header {
+            ForwardedHeader::Forwarded => forwarded_elements(&parts.headers)
+                .into_iter()
+                .filter_map(|element| forwarded_element_param(element, "for"))
+                .map(parse_node)
+                .collect(),
+            ForwardedHeader::XForwardedFor => header_list(&parts.headers, &X_FORWARDED_FOR),
+            ForwardedHeader::XRealIp => header_list(&parts.headers, &X_REAL_IP),
+        };
+
+        let mut client = peer;
+        for hop in hops.iter().rev() {
+            match hop {
+                Some(ip) if trusted.contains(*ip) => client = *ip,
+                Some(ip) => return Ok(Self(*ip)),
+                None => break,
+            }
+        }
+        Ok(Self(client))
+    }
+}
+
+saas_core::__impl_deref!(ClientIp: IpAddr);
+
+/// The proxies whose forwarding headers are trusted.
+///
+/// Used as a layer, the same way as [`DefaultBodyLimit`](super::DefaultBodyLimit). It is read by
+/// [`ClientIp`], and by [`Host`](super::Host) which only accepts `Forwarded` and
+/// `X-Forwarded-Host` from trusted proxies when this layer is added.
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct TrustedProxies {
+    cidrs: Vec<IpCidr>,
+    header: ForwardedHeader,
+}
+
+/// The forwarding header that trusted proxies append the address of their peer to.
+///
+/// See [`TrustedProxies::header`].
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ForwardedHeader {
+    /// The `for` parameters of `Forwarded`, with `host` and `proto` for [`Host`](super::Host)
+    /// and [`Scheme`](super::Scheme).
+    Forwarded,
+    /// `X-Forwarded-For`, with `X-Forwarded-Host` and `X-Forwarded-Proto` for
+    /// [`Host`](super::Host) and [`Scheme`](super::Scheme).
+    #[default]
+    XForwardedFor,
+    /// `X-Real-IP`, which has no headers for the host and scheme.
+    XRealIp,
+}
+
+impl TrustedProxies {
+    /// Create a new `TrustedProxies` that doesn't trust any proxy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust the proxies in `cidr`.
+    pub fn trust(mut self, cidr: IpCidr) -> Self {
+        self.cidrs.push(cidr);
+        self
+    }
+
+    /// Set the forwarding header the trusted proxies append to.
+    ///
+    /// Only this header is read, so a client can't pick a different one. Defaults to
+    /// [`ForwardedHeader::XForwardedFor`].
+    pub fn header(mut self, header: ForwardedHeader) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Returns `true` if `ip` is a trusted proxy.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.cidrs.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// The parameter `name` of the forwarding header, such as `host` or `proto`, as recorded by
+    /// the first trusted proxy the request went through. `x_forwarded` is the `X-Forwarded-*`
+    /// header for the parameter.
+    ///
+    /// Each value is paired with the hop its proxy received the request from, and they are
+    /// walked from right to left for as long as that hop is trusted.
+    pub(crate) fn forwarded_param<'a>(
+        &self,
+        extensions: &Extensions,
+        headers: &'a HeaderMap,
+        name: &str,
+        x_forwarded: &HeaderName,
+    ) -> Option<&'a str> {
+        if !peer_ip(extensions).is_some_and(|peer| self.contains(peer)) {
+            return None;
+        }
+
+        let entries: Vec<(Option<IpAddr>, Option<&str>)> = match self.header {
+            ForwardedHeader::Forwarded => forwarded_elements(headers)
+                .into_iter()
+                .map(|element| {
+                    (
+                        forwarded_element_param(element, "for").and_then(parse_node),
+                        forwarded_element_param(element, name),
+                    )
+                })
+                .collect(),
+            ForwardedHeader::XForwardedFor => {
+                let hops = header_list(headers, &X_FORWARDED_FOR);
+                let values = header_values(headers, x_forwarded);
+                // proxies append to both headers, so the values line up from the right
+                hops.into_iter()
+                    .rev()
+                    .zip(values.into_iter().rev().map(Some))
+                    .rev()
+                    .collect()
+            }
+            ForwardedHeader::XRealIp => return None,
+        };
+
+        let mut value = None;
+        for (hop, param) in entries.into_iter().rev() {
+            match param {
+                Some(param) => value = Some(param),
+                None => break,
+            }
+            match hop {
+                Some(ip) if self.contains(ip) => {}
+                _ => break,
+            }
+        }
+        value
+    }
+}
+
+impl<S> Layer<S> for TrustedProxies {
+    type Service = <Extension<Self> as Layer<S>>::Service;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Extension(self.clone()).layer(inner)
+    }
+}
+
+fn peer_ip(extensions: &Extensions) -> Option<IpAddr> {
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .or_else(|| {
+            extensions
+                .get::<MockConnectInfo<SocketAddr>>()
+                .map(|MockConnectInfo(addr)| addr.ip())
+        })
+}
+
+/// Every element of the `Forwarded` headers, in order.
+fn forwarded_elements(headers: &HeaderMap) -> Vec<&str> {
+    header_values(headers, &FORWARDED)
+}
+
+fn forwarded_element_param<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    element.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+fn header_list(headers: &HeaderMap, name: &HeaderName) -> Vec<Option<IpAddr>> {
+    header_values(headers, name).into_iter().map(parse_node).collect()
+}
+
+/// The comma separated values of every `name` header, in order.
+///
+/// Stops at the first header that isn't valid UTF-8, the values after it can't be attributed to
+/// a hop.
+fn header_values<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Vec<&'a str> {
+    let mut values = Vec::new();
+    for value in headers.get_all(name) {
+        match value.to_str() {
+            Ok(value) => values.extend(value.split(',').map(str::trim)),
+            Err(_) => break,
+        }
+    }
+    values
+}
+
+/// Parse a node that may have a port, such as `192.0.2.43:47011` or `[2001:db8::1]:4711`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    node.strip_prefix('[')
+        .and_then(|node| node.strip_suffix(']'))
+        .and_then(|ip| ip.parse().ok())
+}
+
+/// A range of IP addresses, such as `10.0.0.0/8` or `fd00::/8`.
+///
+/// A single address without a prefix length, such as `127.0.0.1`, only contains itself.
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Create a new `IpCidr`.
+    ///
+    /// Returns `None` if `prefix_len` is longer than the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        let max = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        (prefix_len <= max).then_some(Self { addr, prefix_len })
+    }
+
+    /// Returns `true` if `ip` is in the range.
+    ///
+    /// IPv4-mapped IPv6 addresses are compared as IPv4 addresses.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, canonical(ip)) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_eq(u32::from(net).into(), u32::from(ip).into(), self.prefix_len, 32)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_eq(u128::from(net), u128::from(ip), self.prefix_len, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+fn prefix_eq(a: u128, b: u128, prefix_len: u8, bits: u32) -> bool {
+    let shift = bits - u32::from(prefix_len);
+    a.checked_shr(shift).unwrap_or(0) == b.checked_shr(shift).unwrap_or(0)
+}
+
+impl From<IpAddr> for IpCidr {
+    fn from(addr: IpAddr) -> Self {
+        let prefix_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        Self { addr, prefix_len }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = InvalidIpCidr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((addr, prefix_len)) => {
+                let addr = addr.parse().map_err(|_| InvalidIpCidr)?;
+                let prefix_len = prefix_len.parse().map_err(|_| InvalidIpCidr)?;
+                Self::new(addr, prefix_len).ok_or(InvalidIpCidr)
+            }
+            None => s.parse::<IpAddr>().map(Self::from).map_err(|_| InvalidIpCidr),
+        }
+    }
+}
+
+/// Error returned when parsing an [`IpCidr`] fails.
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InvalidIpCidr;
+
+impl fmt::Display for InvalidIpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid IP address range")
+    }
+}
+
+impl Error for InvalidIpCidr {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Request;
+
+    async fn client_ip(peer: &str, header: ForwardedHeader, headers: &[(&str, &str)]) -> IpAddr {
+        let mut req = Request::builder();
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+        let (mut parts, _) = req.body(()).unwrap().into_parts();
+        parts
+            .extensions
+            .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 1234)));
+        parts.extensions.insert(
+            TrustedProxies::new()
+                .trust("10.0.0.0/8".parse().unwrap())
+                .trust("::1".parse().unwrap())
+                .header(header),
+        );
+        ClientIp::from_request_parts(&mut parts, &()).await.unwrap().0
+    }
+
+    #[tokio::test]
+    async fn walks_hops_right_to_left() {
+        let xff = [("x-forwarded-for", "1.1.1.1, 2.2.2.2, 10.0.0.2")];
+        let header = ForwardedHeader::XForwardedFor;
+        assert_eq!(client_ip("10.0.0.1", header, &xff).await, "2.2.2.2".parse::<IpAddr>().unwrap());
+        // untrusted peers can't spoof the header
+        assert_eq!(client_ip("3.3.3.3", header, &xff).await, "3.3.3.3".parse::<IpAddr>().unwrap());
+
+        let forwarded = [(
+            "forwarded",
+            "for=1.1.1.1, for=\"[2001:db8::1]:4711\";proto=https",
+        )];
+        assert_eq!(
+            client_ip("::1", ForwardedHeader::Forwarded, &forwarded).await,
+            "2001:db8::1".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn only_reads_the_configured_header() {
+        // the proxy appends to `X-Forwarded-For`, `Forwarded` was sent by the client
+        let headers = [
+            ("forwarded", "for=1.1.1.1"),
+            ("x-forwarded-for", "2.2.2.2"),
+        ];
+        assert_eq!(
+            client_ip("10.0.0.1", ForwardedHeader::XForwardedFor, &headers).await,
+            "2.2.2.2".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn forwarded_param_walks_trusted_hops() {
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 1234))));
+        let trusted = TrustedProxies::new()
+            .trust("10.0.0.0/8".parse().unwrap())
+            .header(ForwardedHeader::Forwarded);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            FORWARDED,
+            "for=1.1.1.1;host=spoofed.com, for=2.2.2.2;host=example.com, for=10.0.0.2;host=internal"
+                .parse()
+                .unwrap(),
+        );
+        let x_forwarded_host = HeaderName::from_static("x-forwarded-host");
+        assert_eq!(
+            trusted.forwarded_param(&extensions, &headers, "host", &x_forwarded_host),
+            Some("example.com")
+        );
+
+        let trusted = trusted.header(ForwardedHeader::XForwardedFor);
+        let mut headers = HeaderMap::new();
+        headers.insert(X_FORWARDED_FOR, "1.1.1.1, 2.2.2.2".parse().unwrap());
+        headers.insert(&x_forwarded_host, "spoofed.com, example.com".parse().unwrap());
+        assert_eq!(
+            trusted.forwarded_param(&extensions, &headers, "host", &x_forwarded_host),
+            Some("example.com")
+        );
+    }
+
+    #[test]
+    fn cidr() {
+        let cidr = "10.0.0.0/8".parse::<IpCidr>().unwrap();
+        assert!(cidr.contains("10.1.2.3".parse().unwrap()));
+        assert!(cidr.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+        assert!("0.0.0.0/0".parse::<IpCidr>().unwrap().contains("8.8.8.8".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Cookie parsing and cookie jar management.
+//!
+//! See [`CookieJar`], [`SignedCookieJar`], and [`PrivateCookieJar`] for more details.
+
+use async_trait::async_trait;
+use http::{
+    header::{COOKIE, SET_COOKIE},
+    request::Parts,
+    HeaderMap,
+};
+use saas_core::{
+    extract::FromRequestParts,
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+use std::convert::Infallible;
+
+#[cfg(feature = "cookie-private")]
+mod private;
+#[cfg(feature = "cookie-signed")]
+mod signed;
+
+#[cfg(feature = "cookie-private")]
+pub use self::private::PrivateCookieJar;
+#[cfg(feature = "cookie-signed")]
+pub use self::signed::SignedCookieJar;
+
+pub use cookie::{Cookie, Expiration, SameSite};
+
+#[cfg(any(feature = "cookie-signed", feature = "cookie-private"))]
+pub use cookie::Key;
+
+/// Extractor that grabs cookies from the request and manages the jar.
+///
+/// Note that methods like [`CookieJar::add`], [`CookieJar::remove`], etc updates the [`CookieJar`]
+/// and returns it. This value _must_ be returned from the handler as part of the response for the
+/// changes to be propagated.
+///
+/// ```rust
+/// use saas::{
+///     Router,
+///     routing::{post, get},
+///     response::{IntoResponse, Redirect},
+///     http::StatusCode,
+///     extract::cookie::{CookieJar, Cookie},
+/// };
+///
+/// async fn create_session(jar: CookieJar) -> Result<(CookieJar, Redirect), StatusCode> {
+///     if let Some(session_id) = authorize_and_create_session().await {
+///         Ok((
+///             // the updated jar must be returned for the changes
+///             // to be included in the response
+///             jar.add(Cookie::new("session_id", session_id)),
+///             Redirect::to("/me"),
+///         ))
+///     } else {
+///         Err(StatusCode::UNAUTHORIZED)
+///     }
+/// }
+///
+/// async fn me(jar: CookieJar) -> Result<(), StatusCode> {
+///     if let Some(session_id) = jar.get("session_id") {
+///         // fetch and render user...
+///         # Ok(())
+///     } else {
+///         Err(StatusCode::UNAUTHORIZED)
+///     }
+/// }
+///
+/// async fn authorize_and_create_session() -> Option<String> {
+///     // authorize the user and create a session...
+///     # todo!()
+/// }
+///
+/// let app = Router::new()
+///     .route("/sessions", post(create_session))
+///     .route("/me", get(me));
+/// # let app: Router = app;
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "cookie")))]
+#[derive(Debug, Default, Clone)]
+pub struct CookieJar {
+    jar: cookie::CookieJar,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CookieJar
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
+
+fn cookies_from_request(headers: &HeaderMap) -> impl Iterator<Item = Cookie<'static>> + '_ {
+    headers
+        .get_all(COOKIE)
+        .into_iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| Cookie::parse_encoded(cookie.to_owned()).ok())
+}
+
+impl CookieJar {
+    /// Create a new `CookieJar` from a map of request headers.
+    ///
+    /// The cookies in `headers` will be added to the jar.
+    ///
+    /// This is intended to be used in middleware and other places where it might be difficult to
+    /// run extractors. Normally you should create `CookieJar`s through [`FromRequestParts`].
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut jar = cookie::CookieJar::new();
+        for cookie in cookies_from_request(headers) {
+            jar.add_original(cookie);
+        }
+        Self { jar }
+    }
+
+    /// Create a new empty `CookieJar`.
+    ///
+    /// This is intended to be used in middleware and other places where it might be difficult to
+    /// run extractors. Normally you should create `CookieJar`s through [`FromRequestParts`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a cookie from the jar.
+    pub fn get(&self, name: &str) -> Option<&Cookie<'static>> {
+        self.jar.get(name)
+    }
+
+    /// Remove a cookie from the jar.
+    ///
+    /// A removal cookie is emitted with the response so the client deletes it.
+    #[must_use]
+    pub fn remove(mut self, cookie: Cookie<'static>) -> Self {
+        self.jar.remove(cookie);
+        self
+    }
+
+    /// Add a cookie to the jar.
+    ///
+    /// The value will automatically be percent-encoded.
+    #[must_use]
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, cookie: Cookie<'static>) -> Self {
+        self.jar.add(cookie);
+        self
+    }
+
+    /// Get an iterator over all cookies in the jar.
+    pub fn iter(&self) -> impl Iterator<Item = &'_ Cookie<'static>> {
+        self.jar.iter()
+    }
+}
+
+impl IntoResponseParts for CookieJar {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        set_cookies(self.jar, res.headers_mut());
+        Ok(res)
+    }
+}
+
+impl IntoResponse for CookieJar {
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+// Only the delta is emitted, cookies that came with the request and weren't changed are left
+// alone. `append` is used so several `Set-Cookie` headers can live side by side, the same way
+// `AppendHeaders` does it.
+fn set_cookies(jar: cookie::CookieJar, headers: &mut HeaderMap) {
+    for cookie in jar.delta() {
+        if let Ok(header_value) = cookie.encoded().to_string().parse() {
+            headers.append(SET_COOKIE, header_value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changed_cookies_are_set() {
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, "keep=1; drop=2".parse().unwrap());
+
+        let jar = CookieJar::from_headers(&headers);
+        assert_eq!(jar.get("keep").unwrap().value(), "1");
+
+        let res = jar
+            .add(Cookie::new("added", "a b"))
+            .remove(Cookie::named("drop"))
+            .into_response();
+
+        let set_cookies = res
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(set_cookies.len(), 2);
+        assert!(set_cookies.iter().any(|c| c.starts_with("added=a%20b")));
+        assert!(set_cookies.iter().any(|c| c.starts_with("drop=;")));
+        assert!(!set_cookies.iter().any(|c| c.starts_with("keep=")));
+    }
+}
@@ -0,0 +1,311 @@
+use super::{cookies_from_request, set_cookies};
+use crate::extract::cookie::{Cookie, Key};
+use async_trait::async_trait;
+use cookie::PrivateJar;
+use http::{request::Parts, HeaderMap};
+use saas_core::{
+    extract::{FromRef, FromRequestParts},
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+use std::{convert::Infallible, fmt, marker::PhantomData};
+
+/// Extractor that grabs private cookies from the request and manages the jar.
+///
+/// All cookies will be encrypted and authenticated with a [`Key`]. The values cannot be read or
+/// tampered with by the client.
+///
+/// The key is taken from the state through [`FromRef`], so rotating it only means changing the
+/// state.
+///
+/// Note that methods like [`PrivateCookieJar::add`], [`PrivateCookieJar::remove`], etc updates the
+/// [`PrivateCookieJar`] and returns it. This value _must_ be returned from the handler as part of
+/// the response for the changes to be propagated.
+///
+/// ```rust
+/// use saas::{
+///     Router,
+///     routing::{post, get},
+///     extract::{FromRef, cookie::{PrivateCookieJar, Cookie, Key}},
+///     response::{IntoResponse, Redirect},
+///     http::StatusCode,
+/// };
+///
+/// async fn create_session(jar: PrivateCookieJar) -> (PrivateCookieJar, Redirect) {
+///     (jar.add(Cookie::new("session_id", "...")), Redirect::to("/me"))
+/// }
+///
+/// async fn me(jar: PrivateCookieJar) -> Result<(), StatusCode> {
+///     jar.get("session_id").map(|_| ()).ok_or(StatusCode::UNAUTHORIZED)
+/// }
+///
+/// // our application state
+/// #[derive(Clone)]
+/// struct AppState {
+///     // that holds the key used to encrypt cookies
+///     key: Key,
+/// }
+///
+/// // this impl tells `PrivateCookieJar` how to access the key from our state
+/// impl FromRef<AppState> for Key {
+///     fn from_ref(state: &AppState) -> Self {
+///         state.key.clone()
+///     }
+/// }
+///
+/// let state = AppState {
+///     // Generate a secure key
+///     //
+///     // You probably don't wanna generate a new one each time the app starts though
+///     key: Key::generate(),
+/// };
+///
+/// let app = Router::new()
+///     .route("/sessions", post(create_session))
+///     .route("/me", get(me))
+///     .with_state(state);
+/// # let _: saas::Router = app;
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "cookie-private")))]
+pub struct PrivateCookieJar<K = Key> {
+    jar: cookie::CookieJar,
+    key: Key,
+    // The key used to extract the key. Allows users to use multiple keys for different
+    // jars. Maybe a library wants its own key.
+    _marker: PhantomData<K>,
+}
+
+impl<K> fmt::Debug for PrivateCookieJar<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateCookieJar")
+            .field("jar", &self.jar)
+            .field("key", &"REDACTED")
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<S, K> FromRequestParts<S> for PrivateCookieJar<K>
+where
+    S: Send + Sync,
+    K: FromRef<S> + Into<Key>,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let key = K::from_ref(state).into();
+        let PrivateCookieJar {
+            jar,
+            key,
+            _marker: _,
+        } = PrivateCookieJar::from_headers(&parts.headers, key);
+        Ok(PrivateCookieJar {
+            jar,
+            key,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl PrivateCookieJar {
+    /// Create a new `PrivateCookieJar` from a map of request headers.
+    ///
+    /// The valid cookies in `headers` will be added to the jar, cookies that cannot be decrypted
+    /// are dropped.
+    ///
+    /// This is intended to be used in middleware and other places where it might be difficult to
+    /// run extractors. Normally you should create `PrivateCookieJar`s through [`FromRequestParts`].
+    pub fn from_headers(headers: &HeaderMap, key: Key) -> Self {
+        let mut jar = cookie::CookieJar::new();
+        let mut private_jar = jar.private_mut(&key);
+        for cookie in cookies_from_request(headers) {
+            if let Some(cookie) = private_jar.decrypt(cookie) {
+                private_jar.add_original(cookie);
+            }
+        }
+
+        Self {
+            jar,
+            key,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create a new empty `PrivateCookieJar`.
+    ///
+    /// This is intended to be used in middleware and other places where it might be difficult to
+    /// run extractors. Normally you should create `PrivateCookieJar`s through [`FromRequestParts`].
+    pub fn new(key: Key) -> Self {
+        Self {
+            jar: Default::default(),
+            key,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K> PrivateCookieJar<K> {
+    /// Get a cookie from the jar.
+    ///
+    /// If the cookie exists and can be decrypted then it is returned in plaintext.
+    pub fn get(&self, name: &str) -> Option<Cookie<'static>> {
+        self.private_jar().get(name)
+    }
+
+    /// Remove a cookie from the jar.
+    #[must_use]
+    pub fn remove(mut self, cookie: Cookie<'static>) -> Self {
+        self.private_jar_mut().remove(cookie);
+        self
+    }
+
+    /// Add a cookie to the jar.
+    ///
+    /// The value will automatically be percent-encoded.
+    #[must_use]
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, cookie: Cookie<'static>) -> Self {
+        self.private_jar_mut().add(cookie);
+        self
+    }
+
+    /// Authenticates and decrypts `cookie`, returning the plaintext version if decryption succeeds
+    /// or `None` otherwise.
+    pub fn decrypt(&self, cookie: Cookie<'static>) -> Option<Cookie<'static>> {
+        self.private_jar().decrypt(cookie)
+    }
+
+    /// Get an iterator over all cookies in the jar.
+    ///
+    /// Only cookies that can be decrypted are yielded by the iterator.
+    pub fn iter(&self) -> impl Iterator<Item = Cookie<'static>> + '_ {
+        PrivateCookieJarIter {
+            jar: self,
+            iter: self.jar.iter(),
+        }
+    }
+
+    fn private_jar(&self) -> PrivateJar<&'_ cookie::CookieJar> {
+        self.jar.private(&self.key)
+    }
+
+    fn private_jar_mut(&mut self) -> PrivateJar<&'_ mut cookie::CookieJar> {
+        self.jar.private_mut(&self.key)
+    }
+}
+
+impl<K> IntoResponseParts for PrivateCookieJar<K> {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        set_cookies(self.jar, res.headers_mut());
+        Ok(res)
+    }
+}
+
+impl<K> IntoResponse for PrivateCookieJar<K> {
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+struct PrivateCookieJarIter<'a, K> {
+    jar: &'a PrivateCookieJar<K>,
+    iter: cookie::Iter<'a>,
+}
+
+impl<'a, K> Iterator for PrivateCookieJarIter<'a, K> {
+    type Item = Cookie<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let cookie = self.iter.next()?;
+
+            if let Some(cookie) = self.jar.get(cookie.name()) {
+                return Some(cookie);
+            }
+        }
+    }
+}
+
+impl<K> Clone for PrivateCookieJar<K> {
+    fn clone(&self) -> Self {
+        Self {
+            jar: self.jar.clone(),
+            key: self.key.clone(),
+            _marker: self._marker,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{
+        header::{COOKIE, SET_COOKIE},
+        Request,
+    };
+
+    // The `Cookie` request header a browser would send back for the cookies set by `res`.
+    fn cookie_header(res: &Response) -> String {
+        res.headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().unwrap().split(';').next().unwrap())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    async fn extract(cookie: &str, key: &Key) -> PrivateCookieJar {
+        let (mut parts, _) = Request::builder()
+            .header(COOKIE, cookie)
+            .body(())
+            .unwrap()
+            .into_parts();
+        PrivateCookieJar::<Key>::from_request_parts(&mut parts, key)
+            .await
+            .unwrap()
+    }
+
+    fn tamper(cookie: &str) -> String {
+        let (name, value) = cookie.split_once('=').unwrap();
+        let mut value = value.as_bytes().to_vec();
+        let idx = value.len() / 2;
+        value[idx] = if value[idx] == b'A' { b'B' } else { b'A' };
+        format!("{}={}", name, String::from_utf8(value).unwrap())
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let key = Key::generate();
+        let res = PrivateCookieJar::new(key.clone())
+            .add(Cookie::new("session", "alice"))
+            .into_response();
+        let cookie = cookie_header(&res);
+        // private cookies are encrypted
+        assert!(cookie.starts_with("session="));
+        assert!(!cookie.contains("alice"));
+
+        let jar = extract(&cookie, &key).await;
+        assert_eq!(jar.get("session").unwrap().value(), "alice");
+        assert_eq!(jar.iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn tampered_cookies_are_rejected() {
+        let key = Key::generate();
+        let res = PrivateCookieJar::new(key.clone())
+            .add(Cookie::new("session", "alice"))
+            .into_response();
+        let cookie = cookie_header(&res);
+
+        let jar = extract(&tamper(&cookie), &key).await;
+        assert!(jar.get("session").is_none());
+
+        // plaintext cookies and cookies encrypted with another key are dropped too
+        let jar = extract("session=alice", &key).await;
+        assert!(jar.get("session").is_none());
+        let jar = extract(&cookie, &Key::generate()).await;
+        assert!(jar.get("session").is_none());
+        assert_eq!(jar.iter().count(), 0);
+    }
+}
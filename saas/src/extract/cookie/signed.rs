@@ -0,0 +1,314 @@
+use super::{cookies_from_request, set_cookies};
+use crate::extract::cookie::{Cookie, Key};
+use async_trait::async_trait;
+use cookie::SignedJar;
+use http::{request::Parts, HeaderMap};
+use saas_core::{
+    extract::{FromRef, FromRequestParts},
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+use std::{convert::Infallible, fmt, marker::PhantomData};
+
+/// Extractor that grabs signed cookies from the request and manages the jar.
+///
+/// All cookies will be signed and verified with a [`Key`]. Do not use this to store private data
+/// as the values are still transmitted in plaintext.
+///
+/// The key is taken from the state through [`FromRef`], so rotating it only means changing the
+/// state.
+///
+/// Note that methods like [`SignedCookieJar::add`], [`SignedCookieJar::remove`], etc updates the
+/// [`SignedCookieJar`] and returns it. This value _must_ be returned from the handler as part of
+/// the response for the changes to be propagated.
+///
+/// ```rust
+/// use saas::{
+///     Router,
+///     routing::{post, get},
+///     extract::{FromRef, cookie::{SignedCookieJar, Cookie, Key}},
+///     response::{IntoResponse, Redirect},
+///     http::StatusCode,
+/// };
+///
+/// async fn create_session(jar: SignedCookieJar) -> (SignedCookieJar, Redirect) {
+///     (jar.add(Cookie::new("session_id", "...")), Redirect::to("/me"))
+/// }
+///
+/// async fn me(jar: SignedCookieJar) -> Result<(), StatusCode> {
+///     jar.get("session_id").map(|_| ()).ok_or(StatusCode::UNAUTHORIZED)
+/// }
+///
+/// // our application state
+/// #[derive(Clone)]
+/// struct AppState {
+///     // that holds the key used to sign cookies
+///     key: Key,
+/// }
+///
+/// // this impl tells `SignedCookieJar` how to access the key from our state
+/// impl FromRef<AppState> for Key {
+///     fn from_ref(state: &AppState) -> Self {
+///         state.key.clone()
+///     }
+/// }
+///
+/// let state = AppState {
+///     // Generate a secure key
+///     //
+///     // You probably don't wanna generate a new one each time the app starts though
+///     key: Key::generate(),
+/// };
+///
+/// let app = Router::new()
+///     .route("/sessions", post(create_session))
+///     .route("/me", get(me))
+///     .with_state(state);
+/// # let _: saas::Router = app;
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "cookie-signed")))]
+pub struct SignedCookieJar<K = Key> {
+    jar: cookie::CookieJar,
+    key: Key,
+    // The key used to extract the key. Allows users to use multiple keys for different
+    // jars. Maybe a library wants its own key.
+    _marker: PhantomData<K>,
+}
+
+impl<K> fmt::Debug for SignedCookieJar<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignedCookieJar")
+            .field("jar", &self.jar)
+            .field("key", &"REDACTED")
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<S, K> FromRequestParts<S> for SignedCookieJar<K>
+where
+    S: Send + Sync,
+    K: FromRef<S> + Into<Key>,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let key = K::from_ref(state).into();
+        let SignedCookieJar {
+            jar,
+            key,
+            _marker: _,
+        } = SignedCookieJar::from_headers(&parts.headers, key);
+        Ok(SignedCookieJar {
+            jar,
+            key,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl SignedCookieJar {
+    /// Create a new `SignedCookieJar` from a map of request headers.
+    ///
+    /// The valid cookies in `headers` will be added to the jar, cookies with an invalid
+    /// signature are dropped.
+    ///
+    /// This is intended to be used in middleware and other places where it might be difficult to
+    /// run extractors. Normally you should create `SignedCookieJar`s through [`FromRequestParts`].
+    pub fn from_headers(headers: &HeaderMap, key: Key) -> Self {
+        let mut jar = cookie::CookieJar::new();
+        let mut signed_jar = jar.signed_mut(&key);
+        for cookie in cookies_from_request(headers) {
+            if let Some(cookie) = signed_jar.verify(cookie) {
+                signed_jar.add_original(cookie);
+            }
+        }
+
+        Self {
+            jar,
+            key,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create a new empty `SignedCookieJar`.
+    ///
+    /// This is intended to be used in middleware and other places where it might be difficult to
+    /// run extractors. Normally you should create `SignedCookieJar`s through [`FromRequestParts`].
+    pub fn new(key: Key) -> Self {
+        Self {
+            jar: Default::default(),
+            key,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K> SignedCookieJar<K> {
+    /// Get a cookie from the jar.
+    ///
+    /// If the cookie exists and its authenticity and integrity can be verified then it is returned
+    /// in plaintext.
+    pub fn get(&self, name: &str) -> Option<Cookie<'static>> {
+        self.signed_jar().get(name)
+    }
+
+    /// Remove a cookie from the jar.
+    #[must_use]
+    pub fn remove(mut self, cookie: Cookie<'static>) -> Self {
+        self.signed_jar_mut().remove(cookie);
+        self
+    }
+
+    /// Add a cookie to the jar.
+    ///
+    /// The value will automatically be percent-encoded.
+    #[must_use]
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, cookie: Cookie<'static>) -> Self {
+        self.signed_jar_mut().add(cookie);
+        self
+    }
+
+    /// Verifies the authenticity and integrity of `cookie`, returning the plaintext version if
+    /// verification succeeds or `None` otherwise.
+    pub fn verify(&self, cookie: Cookie<'static>) -> Option<Cookie<'static>> {
+        self.signed_jar().verify(cookie)
+    }
+
+    /// Get an iterator over all cookies in the jar.
+    ///
+    /// Only cookies with valid authenticity and integrity are yielded by the iterator.
+    pub fn iter(&self) -> impl Iterator<Item = Cookie<'static>> + '_ {
+        SignedCookieJarIter {
+            jar: self,
+            iter: self.jar.iter(),
+        }
+    }
+
+    fn signed_jar(&self) -> SignedJar<&'_ cookie::CookieJar> {
+        self.jar.signed(&self.key)
+    }
+
+    fn signed_jar_mut(&mut self) -> SignedJar<&'_ mut cookie::CookieJar> {
+        self.jar.signed_mut(&self.key)
+    }
+}
+
+impl<K> IntoResponseParts for SignedCookieJar<K> {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        set_cookies(self.jar, res.headers_mut());
+        Ok(res)
+    }
+}
+
+impl<K> IntoResponse for SignedCookieJar<K> {
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+struct SignedCookieJarIter<'a, K> {
+    jar: &'a SignedCookieJar<K>,
+    iter: cookie::Iter<'a>,
+}
+
+impl<'a, K> Iterator for SignedCookieJarIter<'a, K> {
+    type Item = Cookie<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let cookie = self.iter.next()?;
+
+            if let Some(cookie) = self.jar.get(cookie.name()) {
+                return Some(cookie);
+            }
+        }
+    }
+}
+
+impl<K> Clone for SignedCookieJar<K> {
+    fn clone(&self) -> Self {
+        Self {
+            jar: self.jar.clone(),
+            key: self.key.clone(),
+            _marker: self._marker,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{
+        header::{COOKIE, SET_COOKIE},
+        Request,
+    };
+
+    // The `Cookie` request header a browser would send back for the cookies set by `res`.
+    fn cookie_header(res: &Response) -> String {
+        res.headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().unwrap().split(';').next().unwrap())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    async fn extract(cookie: &str, key: &Key) -> SignedCookieJar {
+        let (mut parts, _) = Request::builder()
+            .header(COOKIE, cookie)
+            .body(())
+            .unwrap()
+            .into_parts();
+        SignedCookieJar::<Key>::from_request_parts(&mut parts, key)
+            .await
+            .unwrap()
+    }
+
+    fn tamper(cookie: &str) -> String {
+        let (name, value) = cookie.split_once('=').unwrap();
+        let mut value = value.as_bytes().to_vec();
+        let idx = value.len() / 2;
+        value[idx] = if value[idx] == b'A' { b'B' } else { b'A' };
+        format!("{}={}", name, String::from_utf8(value).unwrap())
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let key = Key::generate();
+        let res = SignedCookieJar::new(key.clone())
+            .add(Cookie::new("session", "alice"))
+            .into_response();
+        let cookie = cookie_header(&res);
+        // signed cookies aren't encrypted
+        assert!(cookie.ends_with("alice"));
+
+        let jar = extract(&cookie, &key).await;
+        assert_eq!(jar.get("session").unwrap().value(), "alice");
+        assert_eq!(jar.iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn tampered_cookies_are_rejected() {
+        let key = Key::generate();
+        let res = SignedCookieJar::new(key.clone())
+            .add(Cookie::new("session", "alice"))
+            .into_response();
+        let cookie = cookie_header(&res);
+
+        let jar = extract(&cookie.replace("alice", "admin"), &key).await;
+        assert!(jar.get("session").is_none());
+
+        let jar = extract(&tamper(&cookie), &key).await;
+        assert!(jar.get("session").is_none());
+
+        // unsigned cookies and cookies signed with another key are dropped too
+        let jar = extract("session=alice", &key).await;
+        assert!(jar.get("session").is_none());
+        let jar = extract(&cookie, &Key::generate()).await;
+        assert!(jar.get("session").is_none());
+        assert_eq!(jar.iter().count(), 0);
+    }
+}
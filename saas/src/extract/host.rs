@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use http::{request::Parts, HeaderMap, header::FORWARDED};
+use http::{request::Parts, HeaderMap, HeaderName, header::FORWARDED};
 
 use super::{
     rejection::{HostRejection, FailedToResolveHost},
@@ -7,8 +7,15 @@ use super::{
 };
 
 
-const X_FORWARDED_HOST_HEADER_KEY: &str = "X-Forwarded-Host";
+const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
 
+/// Extractor that resolves the hostname of the request.
+///
+/// The hostname is resolved from, in order, `Forwarded`, `X-Forwarded-Host`, `Host` and the
+/// request URI. When [`TrustedProxies`](super::client_ip::TrustedProxies) has been added the
+/// forwarding headers are only used if the peer is a trusted proxy, and only the
+/// [header](super::client_ip::TrustedProxies::header) the proxies append to is read, from right
+/// to left the same way as for [`ClientIp`](super::ClientIp).
 #[derive(Debug, Clone)]
 pub struct Host(pub String);
 
@@ -20,18 +27,10 @@ where
     type Rejection = HostRejection;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        if let Some(host) = parse_forwarded(&parts.headers) {
+        if let Some(host) = forwarded_param(parts, "host", &X_FORWARDED_HOST) {
             return Ok(Host(host.to_owned()));
         }
 
-        if let Some(host) = parts
-            .headers
-            .get(X_FORWARDED_HOST_HEADER_KEY)
-            .and_then(|host| host.to_str().ok())
-        {
-            return Ok(Host(host.to_owned()))
-        }
-
         if let Some(host) = parts
             .headers
             .get(http::header::HOST)
@@ -49,13 +48,37 @@ where
     }
 }
 
+/// Get the parameter `name`, such as `host` or `proto`, from the forwarding headers.
+/// `x_forwarded` is the `X-Forwarded-*` header for the parameter.
+pub(super) fn forwarded_param<'a>(
+    parts: &'a Parts,
+    name: &str,
+    x_forwarded: &HeaderName,
+) -> Option<&'a str> {
+    #[cfg(feature = "tokio")]
+    if let Some(trusted) = parts.extensions.get::<super::client_ip::TrustedProxies>() {
+        return trusted.forwarded_param(&parts.extensions, &parts.headers, name, x_forwarded);
+    }
+
+    // without `TrustedProxies` the headers are always trusted for backwards compatibility
+    parse_forwarded(&parts.headers, name).or_else(|| {
+        parts
+            .headers
+            .get(x_forwarded)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(str::trim)
+    })
+}
+
+/// Get the parameter `name` from the first element of the `Forwarded` header.
 #[allow(warnings)]
-fn parse_forwarded(headers: &HeaderMap) -> Option<&str> {
+fn parse_forwarded<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
     let forwarded_values = headers.get(FORWARDED)?.to_str().ok()?;
     let first_value = forwarded_values.split(",").nth(0)?;
     first_value.split(";").find_map(|pair| {
         let (key, value) = pair.split_once("=")?;
-        key.trim().eq_ignore_ascii_case("host")
+        key.trim().eq_ignore_ascii_case(name)
         .then(|| value.trim().trim_matches('"'))
     })
 }
\ No newline at end of file
@@ -4,7 +4,10 @@ use async_trait::async_trait;
 use http::request::Parts;
 use saas_core::extract::{FromRequestParts};
 
-use crate::routing::{RouteId, NEST_TAIL_PARAM_CAPTURE};
+use crate::{
+    extension::insert_cloneable,
+    routing::{RouteId, NEST_TAIL_PARAM_CAPTURE},
+};
 
 use super::rejection::{MatchedPathRejection, MatchedPathMissing};
 
@@ -58,10 +61,10 @@ pub(crate) fn set_matched_path_for_request(
     let matched_path = append_nested_matched_path(matched_path, extensions);
 
     if matched_path.ends_with(NEST_TAIL_PARAM_CAPTURE) {
-        extensions.insert(MatchedNestedPath(matched_path));
+        insert_cloneable(extensions, MatchedNestedPath(matched_path));
         debug_assert!(extensions.remove::<MatchedPath>().is_none());
     } else {
-        extensions.insert(MatchedPath(matched_path));
+        insert_cloneable(extensions, MatchedPath(matched_path));
         extensions.remove::<MatchedNestedPath>();
     }
 }
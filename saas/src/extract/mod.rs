@@ -1,17 +1,24 @@
 
+pub mod body_stream;
+#[cfg(feature = "tokio")]
+pub mod client_ip;
 #[cfg(feature = "tokio")]
 pub mod connect_info;
+#[cfg(feature = "cookie")]
+pub mod cookie;
 pub mod path;
 pub mod rejection;
 
 #[cfg(feature = "ws")]
 pub mod ws;
 
+mod base_url;
 mod host;
 mod raw_form;
 mod raw_query;
 mod request_parts;
 mod state;
+mod with_rejection;
 
 use http::{HeaderMap, header};
 pub use saas_core::extract::{DefaultBodyLimit, FromRef, FromRequest, FromRequestParts, Request};
@@ -19,20 +26,43 @@ pub use saas_core::extract::{DefaultBodyLimit, FromRef, FromRequest, FromRequest
 #[doc(inline)]
 #[allow(deprecated)]
 pub use self::{
+    base_url::{BaseUrl, RequestUrl, Scheme, TlsConnection},
+    body_stream::BodyStream,
     host::Host,
     path::{Path, RawPathParams},
     raw_form::RawForm,
     raw_query::RawQuery,
     state::State,
+    with_rejection::WithRejection,
 };
 
 #[doc(inline)]
 #[cfg(feature = "tokio")]
-pub use self::connect_info::ConnectInfo;
+pub use self::{client_ip::ClientIp, connect_info::ConnectInfo};
+
+#[doc(no_inline)]
+#[cfg(feature = "cookie")]
+pub use self::cookie::CookieJar;
 
 #[doc(no_inline)]
 #[cfg(feature = "json")]
-pub use crate::Json;
+pub use crate::{Json, JsonConfig};
+
+#[doc(no_inline)]
+#[cfg(feature = "msgpack")]
+pub use crate::MsgPack;
+
+#[doc(no_inline)]
+#[cfg(feature = "cbor")]
+pub use crate::Cbor;
+
+#[doc(no_inline)]
+#[cfg(feature = "protobuf")]
+pub use crate::Protobuf;
+
+#[doc(no_inline)]
+#[cfg(feature = "xml")]
+pub use crate::Xml;
 
 #[doc(no_inline)]
 pub use crate::Extension;
@@ -60,10 +90,23 @@ mod query;
 #[cfg(feature = "query")]
 pub use self::query::Query;
 
+#[cfg(feature = "query")]
+mod nested_query;
+
+#[cfg(feature = "query")]
+pub use self::nested_query::NestedQuery;
+
 #[cfg(feature = "original-uri")]
 #[doc(inline)]
 pub use self::request_parts::OriginalUri;
 
+#[cfg(feature = "validation")]
+pub mod valid;
+
+#[cfg(feature = "validation")]
+#[doc(inline)]
+pub use self::valid::Valid;
+
 #[cfg(feature = "ws")]
 #[doc(inline)]
 pub use self::ws::WebSocketUpgrade;
@@ -0,0 +1,494 @@
+//! Extractor that parses `multipart/form-data` requests commonly used with file uploads.
+//!
+//! See [`Multipart`] for more details.
+
+use super::{
+    rejection::{AnyRejection, BodyLimitExceeded},
+    DefaultBodyLimit, FromRequest, Request,
+};
+use crate::{body::Bytes, Extension};
+use async_trait::async_trait;
+use futures_util::stream::Stream;
+use http::{
+    header::{HeaderMap, CONTENT_TYPE},
+    StatusCode,
+};
+use saas_core::{
+    body::Body,
+    response::{IntoResponse, Response},
+    RequestExt,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+
+#[doc(inline)]
+pub use crate::extract::rejection::{InvalidBoundary, MultipartRejection};
+
+/// Extractor that parses `multipart/form-data` requests (commonly used with file uploads).
+///
+/// The body is streamed, fields are yielded one at a time with [`Multipart::next_field`].
+/// The whole body is limited by [`DefaultBodyLimit`](super::DefaultBodyLimit), limits for
+/// single fields can be configured with [`MultipartLimits`].
+///
+/// ```rust,no_run
+/// use saas::{extract::Multipart, routing::post, Router};
+///
+/// async fn upload(mut multipart: Multipart) {
+///     while let Some(mut field) = multipart.next_field().await.unwrap() {
+///         let name = field.name().unwrap().to_owned();
+///         let data = field.bytes().await.unwrap();
+///
+///         println!("Length of `{}` is {} bytes", name, data.len());
+///     }
+/// }
+///
+/// let app = Router::new().route("/upload", post(upload));
+/// # let _: Router = app;
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+#[derive(Debug)]
+pub struct Multipart {
+    inner: multer::Multipart<'static>,
+    limit: Option<usize>,
+}
+
+#[async_trait]
+impl<S> FromRequest<S> for Multipart
+where
+    S: Send + Sync,
+{
+    type Rejection = MultipartRejection;
+
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        let boundary = parse_boundary(req.headers()).ok_or(InvalidBoundary)?;
+        let constraints = req
+            .extensions()
+            .get::<MultipartLimits>()
+            .map(MultipartLimits::constraints)
+            .unwrap_or_else(multer::Constraints::new);
+
+        let limit = DefaultBodyLimit::__resolve(req.extensions(), req.headers());
+        let body = match req.into_limited_body() {
+            Ok(limited) => Body::new(limited),
+            Err(unlimited) => unlimited,
+        };
+
+        let multipart = multer::Multipart::with_constraints(body, boundary, constraints);
+        Ok(Self {
+            inner: multipart,
+            limit,
+        })
+    }
+}
+
+impl Multipart {
+    /// Yields the next [`Field`] if available.
+    pub async fn next_field(&mut self) -> Result<Option<Field<'_>>, MultipartError> {
+        let limit = self.limit;
+        let field = self
+            .inner
+            .next_field()
+            .await
+            .map_err(|err| MultipartError::from_multer(err, limit))?;
+
+        if let Some(field) = field {
+            Ok(Some(Field {
+                inner: field,
+                multipart: self,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// A single field in a multipart stream.
+#[derive(Debug)]
+pub struct Field<'a> {
+    inner: multer::Field<'static>,
+    // multer requires there to only be one live `multer::Field` at any point. This enforces that
+    // statically, which multer does not do, it returns an error instead.
+    multipart: &'a mut Multipart,
+}
+
+impl<'a> Stream for Field<'a> {
+    type Item = Result<Bytes, MultipartError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let limit = self.multipart.limit;
+        Pin::new(&mut self.inner)
+            .poll_next(cx)
+            .map_err(|err| MultipartError::from_multer(err, limit))
+    }
+}
+
+impl<'a> Field<'a> {
+    /// The field name found in the
+    /// [`Content-Disposition`](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Disposition)
+    /// header.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    /// The file name found in the
+    /// [`Content-Disposition`](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Disposition)
+    /// header.
+    pub fn file_name(&self) -> Option<&str> {
+        self.inner.file_name()
+    }
+
+    /// Get the [content type](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Type) of the field.
+    pub fn content_type(&self) -> Option<&str> {
+        self.inner.content_type().map(|m| m.as_ref())
+    }
+
+    /// Get a map of headers as [`HeaderMap`].
+    pub fn headers(&self) -> &HeaderMap {
+        self.inner.headers()
+    }
+
+    /// Get the full data of the field as [`Bytes`].
+    pub async fn bytes(self) -> Result<Bytes, MultipartError> {
+        let limit = self.multipart.limit;
+        self.inner
+            .bytes()
+            .await
+            .map_err(|err| MultipartError::from_multer(err, limit))
+    }
+
+    /// Get the full field data as text.
+    pub async fn text(self) -> Result<String, MultipartError> {
+        let limit = self.multipart.limit;
+        self.inner
+            .text()
+            .await
+            .map_err(|err| MultipartError::from_multer(err, limit))
+    }
+
+    /// Stream a chunk of the field data.
+    ///
+    /// When the field data has been exhausted, this will return [`None`].
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, MultipartError> {
+        let limit = self.multipart.limit;
+        self.inner
+            .chunk()
+            .await
+            .map_err(|err| MultipartError::from_multer(err, limit))
+    }
+}
+
+/// Size limits applied to the fields of a [`Multipart`] body.
+///
+/// Used as a layer, the same way as [`DefaultBodyLimit`](super::DefaultBodyLimit). The limit
+/// of the whole body is still controlled by `DefaultBodyLimit`.
+///
+/// ```rust
+/// use saas::{
+///     extract::{multipart::MultipartLimits, Multipart},
+///     routing::post,
+///     Router,
+/// };
+///
+/// async fn upload(multipart: Multipart) {}
+///
+/// let app = Router::new().route(
+///     "/upload",
+///     post(upload).layer(
+///         MultipartLimits::new()
+///             .per_field(64 * 1024)
+///             .for_field("avatar", 10 * 1024 * 1024),
+///     ),
+/// );
+/// # let _: Router = app;
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct MultipartLimits {
+    per_field: Option<u64>,
+    for_field: HashMap<String, u64>,
+}
+
+impl MultipartLimits {
+    /// Create a new `MultipartLimits` without any field limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum size in bytes of every field.
+    pub fn per_field(mut self, limit: u64) -> Self {
+        self.per_field = Some(limit);
+        self
+    }
+
+    /// Set the maximum size in bytes of the field with the given name.
+    ///
+    /// Takes precedence over [`MultipartLimits::per_field`].
+    pub fn for_field(mut self, name: impl Into<String>, limit: u64) -> Self {
+        self.for_field.insert(name.into(), limit);
+        self
+    }
+
+    fn constraints(&self) -> multer::Constraints {
+        let mut size_limit = multer::SizeLimit::new();
+        if let Some(limit) = self.per_field {
+            size_limit = size_limit.per_field(limit);
+        }
+        for (name, limit) in &self.for_field {
+            size_limit = size_limit.for_field(name.clone(), *limit);
+        }
+        multer::Constraints::new().size_limit(size_limit)
+    }
+}
+
+impl<S> Layer<S> for MultipartLimits {
+    type Service = <Extension<Self> as Layer<S>>::Service;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Extension(self.clone()).layer(inner)
+    }
+}
+
+/// Errors associated with parsing `multipart/form-data` requests.
+#[derive(Debug)]
+pub struct MultipartError {
+    source: multer::Error,
+    limit: Option<usize>,
+}
+
+impl MultipartError {
+    fn from_multer(multer: multer::Error, limit: Option<usize>) -> Self {
+        Self {
+            source: multer,
+            limit,
+        }
+    }
+
+    /// Get the response body text used for this rejection.
+    pub fn body_text(&self) -> String {
+        match self.limit {
+            Some(limit) if is_body_limit_error(&self.source) => format!(
+                "Request body is too large: {}",
+                BodyLimitExceeded::__new(limit)
+            ),
+            _ => self.source.to_string(),
+        }
+    }
+
+    /// Get the status code used for this rejection.
+    pub fn status(&self) -> http::StatusCode {
+        status_code_from_multer_error(&self.source)
+    }
+}
+
+fn status_code_from_multer_error(err: &multer::Error) -> StatusCode {
+    match err {
+        multer::Error::UnknownField { .. }
+        | multer::Error::IncompleteFieldData { .. }
+        | multer::Error::IncompleteHeaders
+        | multer::Error::ReadHeaderFailed(..)
+        | multer::Error::DecodeHeaderName { .. }
+        | multer::Error::DecodeContentType(..)
+        | multer::Error::NoBoundary
+        | multer::Error::DecodeHeaderValue { .. }
+        | multer::Error::NoMultipart
+        | multer::Error::IncompleteStream => StatusCode::BAD_REQUEST,
+        multer::Error::FieldSizeExceeded { .. } | multer::Error::StreamSizeExceeded { .. } => {
+            StatusCode::PAYLOAD_TOO_LARGE
+        }
+        multer::Error::StreamReadFailed(inner) => {
+            if let Some(inner) = inner.downcast_ref::<multer::Error>() {
+                return status_code_from_multer_error(inner);
+            }
+
+            if is_body_limit_error(err) {
+                return StatusCode::PAYLOAD_TOO_LARGE;
+            }
+
+            // these are the only errors we can get from the request body
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+// Whether the body was cut off by `DefaultBodyLimit`, as opposed to the field limits of
+// `MultipartLimits`.
+fn is_body_limit_error(err: &multer::Error) -> bool {
+    match err {
+        multer::Error::StreamReadFailed(err) => {
+            if let Some(err) = err.downcast_ref::<multer::Error>() {
+                return is_body_limit_error(err);
+            }
+            err.downcast_ref::<crate::Error>()
+                .and_then(|err| err.source())
+                .and_then(|err| err.downcast_ref::<http_body::LengthLimitError>())
+                .is_some()
+        }
+        _ => false,
+    }
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Error parsing `multipart/form-data` request")
+    }
+}
+
+impl std::error::Error for MultipartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl IntoResponse for MultipartError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body_text = self.body_text();
+        saas_core::__log_rejection!(
+            rejection_type = MultipartError,
+            body_text = &body_text,
+            status = status,
+        );
+        let title = if status == StatusCode::PAYLOAD_TOO_LARGE {
+            "Request body is too large"
+        } else {
+            "Error parsing `multipart/form-data` request"
+        };
+        let mut res = (status, body_text.clone()).into_response();
+        res.extensions_mut().insert(
+            AnyRejection::__new(status, body_text.clone(), self).__with_message(
+                "MultipartError",
+                title,
+                Some(body_text),
+            ),
+        );
+        res
+    }
+}
+
+fn parse_boundary(headers: &HeaderMap) -> Option<String> {
+    let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+    multer::parse_boundary(content_type).ok()
+}
+
+#[test]
+fn traits() {
+    use crate::test_helpers::*;
+    assert_send::<Multipart>();
+    assert_sync::<MultipartLimits>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{routing::post, Router};
+    use tower::ServiceExt;
+
+    const BOUNDARY: &str = "X-BOUNDARY";
+
+    async fn upload(mut multipart: Multipart) -> Result<String, MultipartError> {
+        let mut lengths = Vec::new();
+        while let Some(field) = multipart.next_field().await? {
+            let name = field.name().unwrap_or_default().to_owned();
+            lengths.push(format!("{}={}", name, field.bytes().await?.len()));
+        }
+        Ok(lengths.join(","))
+    }
+
+    fn form_body(fields: &[(&str, usize)]) -> String {
+        let mut body = String::new();
+        for (name, len) in fields {
+            body.push_str(&format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                BOUNDARY,
+                name,
+                "a".repeat(*len),
+            ));
+        }
+        body.push_str(&format!("--{}--\r\n", BOUNDARY));
+        body
+    }
+
+    async fn send(app: Router, content_type: Option<&str>, body: String) -> (StatusCode, String) {
+        let mut req = http::Request::post("/");
+        if let Some(content_type) = content_type {
+            req = req.header(CONTENT_TYPE, content_type);
+        }
+        let res = app.oneshot(req.body(Body::from(body)).unwrap()).await.unwrap();
+        let status = res.status();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn send_form(app: Router, body: String) -> (StatusCode, String) {
+        let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+        send(app, Some(&content_type), body).await
+    }
+
+    #[tokio::test]
+    async fn field_limits() {
+        let app = Router::new().route(
+            "/",
+            post(upload).layer(MultipartLimits::new().per_field(8).for_field("avatar", 32)),
+        );
+
+        let fields = form_body(&[("name", 8), ("avatar", 32)]);
+        let (status, body) = send_form(app.clone(), fields).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "name=8,avatar=32");
+
+        let (status, body) = send_form(app.clone(), form_body(&[("name", 9)])).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(!body.starts_with("Request body is too large"));
+
+        let (status, _) = send_form(app, form_body(&[("avatar", 33)])).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn default_body_limit() {
+        let app = Router::new()
+            .route("/", post(upload))
+            .layer(DefaultBodyLimit::max(256));
+
+        let (status, _) = send_form(app.clone(), form_body(&[("a", 1)])).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = send_form(app, form_body(&[("a", 512)])).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            body,
+            "Request body is too large: length limit of 256 bytes exceeded"
+        );
+
+        let app = Router::new()
+            .route("/", post(upload))
+            .layer(DefaultBodyLimit::disable());
+        let (status, body) = send_form(app, form_body(&[("a", 4 * 1024 * 1024)])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, format!("a={}", 4 * 1024 * 1024));
+    }
+
+    #[tokio::test]
+    async fn invalid_boundary() {
+        let app = Router::new().route("/", post(upload));
+
+        for content_type in [None, Some("multipart/form-data"), Some("text/plain")] {
+            let (status, body) = send(app.clone(), content_type, form_body(&[("a", 1)])).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body, "Invalid `boundary` for `multipart/form-data` request");
+        }
+
+        // a boundary that doesn't match the body
+        let content_type = "multipart/form-data; boundary=other";
+        let (status, _) = send(app, Some(content_type), form_body(&[("a", 1)])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,425 @@
+use std::{collections::HashMap, fmt, vec};
+
+use serde::{
+    de::{DeserializeSeed, Error, IntoDeserializer, MapAccess, SeqAccess, Visitor},
+    Deserializer,
+};
+
+// Keys nested deeper than this, such as `a[b][c]...`, are rejected.
+const MAX_DEPTH: usize = 32;
+
+/// A query string parsed into a tree.
+///
+/// Repeated keys and `key[]` collect into [`Node::Values`], `key[child]` nests into
+/// [`Node::Map`]. Insertion order is kept so indexed arrays (`key[0]`, `key[1]`) and maps
+/// come out the way the client sent them.
+#[derive(Debug)]
+pub(crate) enum Node {
+    Values(Vec<String>),
+    Map(Map),
+}
+
+impl Node {
+    pub(crate) fn parse(query: &str) -> Result<Self, QueryDeserializationError> {
+        let mut root = Map::default();
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            let segments = split_key(&key);
+            if segments.len() > MAX_DEPTH {
+                return Err(QueryDeserializationError::custom(format!(
+                    "key `{key}` is nested deeper than {MAX_DEPTH} levels"
+                )));
+            }
+            insert(&mut root, &segments, value.into_owned())?;
+        }
+        Ok(Node::Map(root))
+    }
+}
+
+/// The children of a [`Node::Map`], in insertion order and indexed by key.
+#[derive(Debug, Default)]
+pub(crate) struct Map {
+    entries: Vec<(String, Node)>,
+    index: HashMap<String, usize>,
+}
+
+impl Map {
+    fn entry(&mut self, key: &str, default: impl FnOnce() -> Node) -> &mut Node {
+        let idx = match self.index.get(key) {
+            Some(idx) => *idx,
+            None => {
+                self.entries.push((key.to_owned(), default()));
+                self.index.insert(key.to_owned(), self.entries.len() - 1);
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[idx].1
+    }
+}
+
+// `filter[status][0]` -> ["filter", "status", "0"], `tag[]` -> ["tag", ""]
+fn split_key(key: &str) -> Vec<&str> {
+    match key.find('[') {
+        Some(start) if start > 0 && key.ends_with(']') => {
+            let mut segments = vec![&key[..start]];
+            segments.extend(key[start + 1..key.len() - 1].split("]["));
+            segments
+        }
+        _ => vec![key],
+    }
+}
+
+fn insert(
+    map: &mut Map,
+    segments: &[&str],
+    value: String,
+) -> Result<(), QueryDeserializationError> {
+    let (name, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+    let is_leaf = matches!(rest, [] | [""]);
+
+    let node = map.entry(name, || {
+        if is_leaf {
+            Node::Values(Vec::new())
+        } else {
+            Node::Map(Map::default())
+        }
+    });
+
+    match (node, is_leaf) {
+        (Node::Values(values), true) => {
+            values.push(value);
+            Ok(())
+        }
+        (Node::Map(children), false) => insert(children, rest, value),
+        _ => Err(QueryDeserializationError::custom(format!(
+            "conflicting nested and plain values for key `{name}`"
+        ))),
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct QueryDeserializationError(String);
+
+impl Error for QueryDeserializationError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Self(msg.to_string())
+    }
+}
+
+impl fmt::Display for QueryDeserializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for QueryDeserializationError {}
+
+macro_rules! parse_value {
+    ($trait_fn:ident, $visit_fn:ident, $ty:literal) => {
+        fn $trait_fn<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            let value = self.into_single_value()?;
+            let v = value.parse().map_err(|_| {
+                QueryDeserializationError::custom(format!(
+                    "cannot parse `{}` as `{}`",
+                    value, $ty
+                ))
+            })?;
+            visitor.$visit_fn(v)
+        }
+    };
+}
+
+pub(crate) struct NodeDeserializer(pub(crate) Node);
+
+impl NodeDeserializer {
+    fn into_single_value(self) -> Result<String, QueryDeserializationError> {
+        match self.0 {
+            Node::Values(mut values) if values.len() == 1 => Ok(values.pop().expect("len is 1")),
+            Node::Values(values) => Err(QueryDeserializationError::custom(format!(
+                "expected a single value, got {}",
+                values.len()
+            ))),
+            Node::Map(_) => Err(QueryDeserializationError::custom(
+                "expected a single value, got nested parameters",
+            )),
+        }
+    }
+
+    fn into_seq(self) -> Result<SeqDeserializer, QueryDeserializationError> {
+        let nodes = match self.0 {
+            Node::Values(values) => values
+                .into_iter()
+                .map(|value| Node::Values(vec![value]))
+                .collect(),
+            Node::Map(map) => {
+                let mut indexed = map
+                    .entries
+                    .into_iter()
+                    .map(|(key, node)| {
+                        key.parse::<usize>().map(|idx| (idx, node)).map_err(|_| {
+                            QueryDeserializationError::custom(format!(
+                                "expected an array index, got `{key}`"
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                indexed.sort_by_key(|(idx, _)| *idx);
+                indexed.into_iter().map(|(_, node)| node).collect::<Vec<_>>()
+            }
+        };
+
+        Ok(SeqDeserializer {
+            iter: nodes.into_iter(),
+        })
+    }
+
+    fn into_map(self) -> Result<MapDeserializer, QueryDeserializationError> {
+        match self.0 {
+            Node::Map(map) => Ok(MapDeserializer {
+                iter: map.entries.into_iter(),
+                value: None,
+            }),
+            Node::Values(_) => Err(QueryDeserializationError::custom(
+                "expected nested parameters, got a plain value",
+            )),
+        }
+    }
+}
+
+impl<'de> Deserializer<'de> for NodeDeserializer {
+    type Error = QueryDeserializationError;
+
+    parse_value!(deserialize_bool, visit_bool, "bool");
+    parse_value!(deserialize_i8, visit_i8, "i8");
+    parse_value!(deserialize_i16, visit_i16, "i16");
+    parse_value!(deserialize_i32, visit_i32, "i32");
+    parse_value!(deserialize_i64, visit_i64, "i64");
+    parse_value!(deserialize_i128, visit_i128, "i128");
+    parse_value!(deserialize_u8, visit_u8, "u8");
+    parse_value!(deserialize_u16, visit_u16, "u16");
+    parse_value!(deserialize_u32, visit_u32, "u32");
+    parse_value!(deserialize_u64, visit_u64, "u64");
+    parse_value!(deserialize_u128, visit_u128, "u128");
+    parse_value!(deserialize_f32, visit_f32, "f32");
+    parse_value!(deserialize_f64, visit_f64, "f64");
+    parse_value!(deserialize_char, visit_char, "char");
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match &self.0 {
+            Node::Values(values) if values.len() == 1 => {}
+            Node::Values(_) => return visitor.visit_seq(self.into_seq()?),
+            Node::Map(_) => return visitor.visit_map(self.into_map()?),
+        }
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_string(self.into_single_value()?)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_byte_buf(self.into_single_value()?.into_bytes())
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // `?page=` is treated the same as a missing `page`
+        let is_empty = matches!(&self.0, Node::Values(values) if values.len() == 1 && values[0].is_empty());
+        if is_empty {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(self.into_seq()?)
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(self.into_map()?)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self.into_single_value()?.into_deserializer())
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+struct SeqDeserializer {
+    iter: vec::IntoIter<Node>,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer {
+    type Error = QueryDeserializationError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.iter
+            .next()
+            .map(|node| seed.deserialize(NodeDeserializer(node)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer {
+    iter: vec::IntoIter<(String, Node)>,
+    value: Option<Node>,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer {
+    type Error = QueryDeserializationError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(NodeDeserializer(value)),
+            None => Err(QueryDeserializationError::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
@@ -0,0 +1,156 @@
+mod de;
+
+use async_trait::async_trait;
+use http::{request::Parts, Uri};
+use saas_core::extract::FromRequestParts;
+use serde::de::DeserializeOwned;
+
+use self::de::{Node, NodeDeserializer};
+
+pub(crate) use self::de::QueryDeserializationError;
+use super::rejection::{FailedToDeserializeQueryString, QueryRejection};
+
+/// Extractor that deserializes query strings with repeated keys and nested structures.
+///
+/// Unlike [`Query`](super::Query), which is backed by `serde_urlencoded`, this supports
+///
+/// - repeated keys: `?tag=a&tag=b` into `Vec<String>`
+/// - bracket arrays: `?tag[]=a&tag[]=b` and `?tag[0]=a&tag[1]=b`
+/// - nested maps and structs: `?filter[status]=open&filter[owner][id]=1`
+///
+/// A single empty value such as `?page=` deserializes into `None` for `Option` fields.
+///
+/// If the query string can't be deserialized the rejection reports the path of the failing
+/// field, the same way [`Json`](super::Json) does.
+///
+/// ```rust,no_run
+/// use saas::{extract::NestedQuery, routing::get, Router};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Search {
+///     tag: Vec<String>,
+///     filter: Filter,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Filter {
+///     status: Option<String>,
+/// }
+///
+/// // `GET /issues?tag=bug&tag=ui&filter[status]=open`
+/// async fn issues(NestedQuery(search): NestedQuery<Search>) {
+///     // ...
+/// }
+///
+/// let app = Router::new().route("/issues", get(issues));
+/// # let _: Router = app;
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "query")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NestedQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for NestedQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = QueryRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::try_from_uri(&parts.uri)
+    }
+}
+
+impl<T> NestedQuery<T>
+where
+    T: DeserializeOwned,
+{
+    /// Attempts to construct a [`NestedQuery`] from a reference to a [`Uri`].
+    pub fn try_from_uri(value: &Uri) -> Result<Self, QueryRejection> {
+        let query = value.query().unwrap_or_default();
+        let node = Node::parse(query).map_err(FailedToDeserializeQueryString::from_err)?;
+        let params = serde_path_to_error::deserialize(NodeDeserializer(node))
+            .map_err(FailedToDeserializeQueryString::from_err)?;
+
+        Ok(NestedQuery(params))
+    }
+}
+
+saas_core::__impl_deref!(NestedQuery);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Search {
+        tag: Vec<String>,
+        ids: Vec<u32>,
+        filter: HashMap<String, String>,
+        page: Option<u32>,
+    }
+
+    fn extract<T: DeserializeOwned>(uri: &'static str) -> Result<T, QueryRejection> {
+        NestedQuery::try_from_uri(&Uri::from_static(uri)).map(|NestedQuery(value)| value)
+    }
+
+    #[test]
+    fn repeated_bracketed_and_nested() {
+        let search: Search =
+            extract("/?tag=a&tag=b&ids%5B%5D=1&ids%5B%5D=2&filter%5Bstatus%5D=open&page=").unwrap();
+
+        assert_eq!(search.tag, ["a", "b"]);
+        assert_eq!(search.ids, [1, 2]);
+        assert_eq!(search.filter["status"], "open");
+        assert_eq!(search.page, None);
+    }
+
+    #[test]
+    fn indexed_arrays_are_ordered() {
+        #[derive(Deserialize)]
+        struct Ids {
+            ids: Vec<u32>,
+        }
+
+        let Ids { ids } = extract("/?ids%5B1%5D=20&ids%5B0%5D=10").unwrap();
+        assert_eq!(ids, [10, 20]);
+    }
+
+    #[test]
+    fn error_reports_field_path() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Outer {
+            filter: Inner,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Inner {
+            owner: u32,
+        }
+
+        let err = extract::<Outer>("/?filter%5Bowner%5D=me").unwrap_err();
+        assert!(err.body_text().contains("filter.owner"), "{}", err.body_text());
+    }
+
+    #[test]
+    fn repeated_nested_keys_are_merged() {
+        let filter: HashMap<String, HashMap<String, Vec<u32>>> =
+            extract("/?f%5Ba%5D=1&f%5Bb%5D=2&f%5Ba%5D=3").unwrap();
+        assert_eq!(filter["f"]["a"], [1, 3]);
+        assert_eq!(filter["f"]["b"], [2]);
+    }
+
+    #[test]
+    fn rejects_deeply_nested_keys() {
+        let uri = format!("/?a{}=1", "%5Bb%5D".repeat(40));
+        let err = NestedQuery::<HashMap<String, String>>::try_from_uri(&uri.parse().unwrap())
+            .unwrap_err();
+        assert!(err.body_text().contains("nested deeper"), "{}", err.body_text());
+    }
+}
@@ -9,7 +9,7 @@ use http::{request::{Parts}, StatusCode};
 use core::{pin::Pin, future::Future};
 use std::{fmt, sync::Arc};
 
-use super::rejection::{PathRejection, MissingPathParams, RawPathParamsRejection};
+use super::rejection::{AnyRejection, MissingPathParams, PathRejection, RawPathParamsRejection};
 
 pub struct Path<T>(pub T);
 
@@ -235,7 +235,23 @@ impl IntoResponse for FailedToDeserializePathParams {
             body_text = self.body_text(),
             status = self.status(),
         );
-        (self.status(), self.body_text()).into_response()
+        let status = self.status();
+        let body_text = self.body_text();
+        let title = if status.is_client_error() {
+            "Invalid URL"
+        } else {
+            "Failed to deserialize path parameters"
+        };
+        let detail = self.0.kind.to_string();
+        let mut res = (status, body_text.clone()).into_response();
+        res.extensions_mut().insert(
+            AnyRejection::__new(status, body_text, self).__with_message(
+                "FailedToDeserializePathParams",
+                title,
+                Some(detail),
+            ),
+        );
+        res
     }
 }
 
@@ -327,6 +343,22 @@ impl std::error::Error for InvalidUtf8InPathParam{}
 
 impl IntoResponse for InvalidUtf8InPathParam {
     fn into_response(self) -> Response {
-        (self.status(), self.body_text()).into_response()
+        saas_core::__log_rejection!(
+            rejection_type = Self,
+            body_text = self.body_text(),
+            status = self.status(),
+        );
+        let status = self.status();
+        let body_text = self.body_text();
+        let detail = self.key.to_string();
+        let mut res = (status, body_text.clone()).into_response();
+        res.extensions_mut().insert(
+            AnyRejection::__new(status, body_text, self).__with_message(
+                "InvalidUtf8InPathParam",
+                "Invalid UTF-8 in path parameter",
+                Some(detail),
+            ),
+        );
+        res
     }
 }
\ No newline at end of file
@@ -6,6 +6,14 @@ use saas_core::__define_rejection as define_rejection;
 pub use crate::extract::path::{FailedToDeserializePathParams, InvalidUtf8InPathParam};
 pub use saas_core::extract::rejection::*;
 
+pub use crate::either::EitherRejection;
+
+#[cfg(feature = "headers")]
+pub use crate::typed_header::{TypedHeaderRejection, TypedHeaderRejectionReason};
+
+#[cfg(feature = "validation")]
+pub use crate::extract::valid::{ValidRejection, ValidationErrors};
+
 #[cfg(feature = "json")]
 define_rejection! {
     #[status = UNPROCESSABLE_ENTITY]
@@ -39,6 +47,27 @@ define_rejection! {
     pub struct MissingJsonContentType;
 }
 
+#[cfg(feature = "json")]
+define_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "JSON body is nested too deeply"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    /// Rejection type for [`Json`](super::Json) used if the body is nested deeper than
+    /// [`JsonConfig::max_depth`](crate::JsonConfig::max_depth) allows.
+    pub struct JsonNestingTooDeep;
+}
+
+#[cfg(feature = "json")]
+define_rejection! {
+    #[status = UNPROCESSABLE_ENTITY]
+    #[body = "JSON body contains a duplicate key"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    /// Rejection type for [`Json`](super::Json) used if an object contains the same key more
+    /// than once and [`JsonConfig::reject_duplicate_keys`](crate::JsonConfig::reject_duplicate_keys)
+    /// is set.
+    pub struct DuplicateJsonKey(Error);
+}
+
 define_rejection! {
     #[status = INTERNAL_SERVER_ERROR]
     #[body = "Missing request extension"]
@@ -73,6 +102,14 @@ define_rejection! {
     pub struct FailedToResolveHost;
 }
 
+define_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Invalid host"]
+    /// Rejection type used if the host resolved by [`BaseUrl`](super::BaseUrl) isn't a valid
+    /// URL authority.
+    pub struct InvalidHost(Error);
+}
+
 define_rejection! {
     #[status = BAD_REQUEST]
     #[body = "Failed to deserialize form"]
@@ -97,6 +134,28 @@ define_rejection! {
     pub struct FailedToDeserializeQueryString(Error);
 }
 
+#[cfg(feature = "multipart")]
+define_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Invalid `boundary` for `multipart/form-data` request"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+    /// Rejection type used if the `boundary` in a `multipart/form-data` is
+    /// missing or invalid.
+    pub struct InvalidBoundary;
+}
+
+#[cfg(feature = "multipart")]
+composite_rejection! {
+    /// Rejection used for [`Multipart`](super::Multipart).
+    ///
+    /// Contains one variant for each way the [`Multipart`](super::Multipart) extractor
+    /// can fail.
+    #[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+    pub enum MultipartRejection {
+        InvalidBoundary,
+    }
+}
+
 composite_rejection! {
     /// Rejection used for [`Query`](super::Query).
     ///
@@ -142,6 +201,257 @@ composite_rejection! {
         JsonDataError,
         JsonSyntaxError,
         MissingJsonContentType,
+        JsonNestingTooDeep,
+        DuplicateJsonKey,
+        BytesRejection,
+    }
+}
+
+#[cfg(feature = "msgpack")]
+define_rejection! {
+    #[status = UNPROCESSABLE_ENTITY]
+    #[body = "Failed to deserialize the MessagePack body into the target type"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+    /// Rejection type for [`MsgPack`](crate::MsgPack).
+    ///
+    /// This rejection is used if the request body is syntactically valid MessagePack but couldn't be
+    /// deserialized into the target type.
+    pub struct MsgPackDataError(Error);
+}
+
+#[cfg(feature = "msgpack")]
+define_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Failed to parse the request body as MessagePack"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+    /// Rejection type for [`MsgPack`](crate::MsgPack).
+    ///
+    /// This rejection is used if the request body didn't contain syntactically valid MessagePack.
+    pub struct MsgPackSyntaxError(Error);
+}
+
+#[cfg(feature = "msgpack")]
+define_rejection! {
+    #[status = UNSUPPORTED_MEDIA_TYPE]
+    #[body = "Expected request with `Content-Type: application/msgpack`"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+    /// Rejection type for [`MsgPack`](crate::MsgPack) used if the `Content-Type`
+    /// header is missing.
+    pub struct MissingMsgPackContentType;
+}
+
+#[cfg(feature = "msgpack")]
+composite_rejection! {
+    /// Rejection used for [`MsgPack`](crate::MsgPack).
+    ///
+    /// Contains one variant for each way the [`MsgPack`](crate::MsgPack) extractor
+    /// can fail.
+    #[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+    pub enum MsgPackRejection {
+        MsgPackDataError,
+        MsgPackSyntaxError,
+        MissingMsgPackContentType,
+        BytesRejection,
+    }
+}
+
+#[cfg(feature = "cbor")]
+define_rejection! {
+    #[status = UNPROCESSABLE_ENTITY]
+    #[body = "Failed to deserialize the CBOR body into the target type"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+    /// Rejection type for [`Cbor`](crate::Cbor).
+    ///
+    /// This rejection is used if the request body is syntactically valid CBOR but couldn't be
+    /// deserialized into the target type.
+    pub struct CborDataError(Error);
+}
+
+#[cfg(feature = "cbor")]
+define_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Failed to parse the request body as CBOR"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+    /// Rejection type for [`Cbor`](crate::Cbor).
+    ///
+    /// This rejection is used if the request body didn't contain syntactically valid CBOR.
+    pub struct CborSyntaxError(Error);
+}
+
+#[cfg(feature = "cbor")]
+define_rejection! {
+    #[status = UNSUPPORTED_MEDIA_TYPE]
+    #[body = "Expected request with `Content-Type: application/cbor`"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+    /// Rejection type for [`Cbor`](crate::Cbor) used if the `Content-Type`
+    /// header is missing.
+    pub struct MissingCborContentType;
+}
+
+#[cfg(feature = "cbor")]
+composite_rejection! {
+    /// Rejection used for [`Cbor`](crate::Cbor).
+    ///
+    /// Contains one variant for each way the [`Cbor`](crate::Cbor) extractor
+    /// can fail.
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+    pub enum CborRejection {
+        CborDataError,
+        CborSyntaxError,
+        MissingCborContentType,
+        BytesRejection,
+    }
+}
+
+#[cfg(feature = "protobuf")]
+define_rejection! {
+    #[status = UNPROCESSABLE_ENTITY]
+    #[body = "Failed to decode the Protobuf body into the target message"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    /// Rejection type for [`Protobuf`](crate::Protobuf).
+    ///
+    /// This rejection is used if the request body couldn't be decoded into the target message.
+    pub struct ProtobufDecodeError(Error);
+}
+
+#[cfg(feature = "protobuf")]
+define_rejection! {
+    #[status = UNSUPPORTED_MEDIA_TYPE]
+    #[body = "Expected request with `Content-Type: application/x-protobuf`"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    /// Rejection type for [`Protobuf`](crate::Protobuf) used if the `Content-Type`
+    /// header is missing.
+    pub struct MissingProtobufContentType;
+}
+
+#[cfg(feature = "protobuf")]
+composite_rejection! {
+    /// Rejection used for [`Protobuf`](crate::Protobuf).
+    ///
+    /// Contains one variant for each way the [`Protobuf`](crate::Protobuf) extractor
+    /// can fail.
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub enum ProtobufRejection {
+        ProtobufDecodeError,
+        MissingProtobufContentType,
+        BytesRejection,
+    }
+}
+
+#[cfg(feature = "xml")]
+define_rejection! {
+    #[status = UNPROCESSABLE_ENTITY]
+    #[body = "Failed to deserialize the XML body into the target type"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+    /// Rejection type for [`Xml`](crate::Xml).
+    ///
+    /// This rejection is used if the request body is well formed XML but couldn't be
+    /// deserialized into the target type.
+    pub struct XmlDataError(Error);
+}
+
+#[cfg(feature = "xml")]
+define_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Failed to parse the request body as XML"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+    /// Rejection type for [`Xml`](crate::Xml).
+    ///
+    /// This rejection is used if the request body wasn't well formed XML.
+    pub struct XmlSyntaxError(Error);
+}
+
+#[cfg(feature = "xml")]
+define_rejection! {
+    #[status = UNSUPPORTED_MEDIA_TYPE]
+    #[body = "Expected request with `Content-Type: application/xml`"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+    /// Rejection type for [`Xml`](crate::Xml) used if the `Content-Type`
+    /// header is missing.
+    pub struct MissingXmlContentType;
+}
+
+#[cfg(feature = "xml")]
+composite_rejection! {
+    /// Rejection used for [`Xml`](crate::Xml).
+    ///
+    /// Contains one variant for each way the [`Xml`](crate::Xml) extractor
+    /// can fail.
+    #[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+    pub enum XmlRejection {
+        XmlDataError,
+        XmlSyntaxError,
+        MissingXmlContentType,
+        BytesRejection,
+    }
+}
+
+#[cfg(feature = "negotiate")]
+define_rejection! {
+    #[status = UNSUPPORTED_MEDIA_TYPE]
+    #[body = "Unsupported `Content-Type` for the request body"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+    /// Rejection type for [`Negotiated`](crate::negotiate::Negotiated) used if the
+    /// `Content-Type` header is missing or none of the codecs can decode it.
+    pub struct UnsupportedContentType;
+}
+
+#[cfg(feature = "negotiate")]
+define_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Failed to decode the request body"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+    /// Rejection type for [`Negotiated`](crate::negotiate::Negotiated).
+    ///
+    /// This rejection is used if the request body isn't syntactically valid for its
+    /// `Content-Type`.
+    pub struct FailedToDecodeBody(Error);
+}
+
+#[cfg(feature = "negotiate")]
+define_rejection! {
+    #[status = UNPROCESSABLE_ENTITY]
+    #[body = "Failed to deserialize the request body into the target type"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+    /// Rejection type for [`Negotiated`](crate::negotiate::Negotiated).
+    ///
+    /// This rejection is used if the request body is syntactically valid but couldn't be
+    /// deserialized into the target type.
+    pub struct FailedToDeserializeBody(Error);
+}
+
+#[cfg(feature = "negotiate")]
+define_rejection! {
+    #[status = NOT_ACCEPTABLE]
+    #[body = "None of the response formats are acceptable"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+    /// Response used by [`Negotiated`](crate::negotiate::Negotiated) if the `Accept` header of
+    /// the request doesn't match any of its codecs.
+    pub struct NotAcceptable;
+}
+
+#[cfg(feature = "decompression")]
+define_rejection! {
+    #[status = UNSUPPORTED_MEDIA_TYPE]
+    #[body = "Unsupported `Content-Encoding` for the request body"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "decompression")))]
+    /// Response used by
+    /// [`RequestDecompressionLayer`](crate::decompression::RequestDecompressionLayer) if the
+    /// request body is encoded with an encoding it can't decode.
+    pub struct UnsupportedContentEncoding(Error);
+}
+
+#[cfg(feature = "negotiate")]
+composite_rejection! {
+    /// Rejection used for [`Negotiated`](crate::negotiate::Negotiated).
+    ///
+    /// Contains one variant for each way the [`Negotiated`](crate::negotiate::Negotiated)
+    /// extractor can fail.
+    #[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+    pub enum NegotiatedRejection {
+        UnsupportedContentType,
+        FailedToDecodeBody,
+        FailedToDeserializeBody,
         BytesRejection,
     }
 }
@@ -188,6 +498,39 @@ composite_rejection! {
     }
 }
 
+composite_rejection! {
+    /// Rejection used for [`BaseUrl`](super::BaseUrl) and [`RequestUrl`](super::RequestUrl).
+    ///
+    /// Contains one variant for each way the [`BaseUrl`](super::BaseUrl) extractor
+    /// can fail.
+    pub enum BaseUrlRejection {
+        FailedToResolveHost,
+        InvalidHost,
+    }
+}
+
+#[cfg(feature = "tokio")]
+define_rejection! {
+    #[status = INTERNAL_SERVER_ERROR]
+    #[body = "Missing `ConnectInfo<SocketAddr>`, serve the app with `into_make_service_with_connect_info`"]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    /// Rejection type used if the [`ClientIp`](super::client_ip::ClientIp) extractor can't
+    /// find the address of the peer.
+    pub struct MissingConnectInfo;
+}
+
+#[cfg(feature = "tokio")]
+composite_rejection! {
+    /// Rejection used for [`ClientIp`](super::client_ip::ClientIp).
+    ///
+    /// Contains one variant for each way the [`ClientIp`](super::client_ip::ClientIp)
+    /// extractor can fail.
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub enum ClientIpRejection {
+        MissingConnectInfo,
+    }
+}
+
 #[cfg(feature = "matched-path")]
 define_rejection! {
     #[status = INTERNAL_SERVER_ERROR]
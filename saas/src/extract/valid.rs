@@ -0,0 +1,562 @@
+//! Extractor wrapper that validates the extracted value.
+//!
+//! See [`Valid`] for more details.
+
+use super::{
+    rejection::{AnyRejection, JsonDataError, JsonRejection, PathRejection},
+    FromRequest, FromRequestParts, Path, Request,
+};
+use crate::Json;
+use async_trait::async_trait;
+use http::{request::Parts, StatusCode};
+use saas_core::response::{IntoResponse, Response};
+use serde_json::json;
+use std::fmt;
+
+#[cfg(feature = "form")]
+use super::{rejection::FormRejection, Form};
+#[cfg(feature = "query")]
+use super::{
+    nested_query::QueryDeserializationError,
+    rejection::{FailedToDeserializeQueryString, QueryRejection},
+    NestedQuery, Query,
+};
+
+/// Extractor that runs [`Validate`] on the value extracted by `E`.
+///
+/// Works with [`Json`], [`Form`](super::Form), [`Query`](super::Query),
+/// [`NestedQuery`](super::NestedQuery) and [`Path`], or any other extractor that implements
+/// [`HasValidate`].
+///
+/// If validation fails the request is rejected with `422 Unprocessable Entity` and a JSON body
+/// listing each failing field:
+///
+/// ```json
+/// { "errors": [{ "field": "email", "message": "must contain an `@`" }] }
+/// ```
+///
+/// Bodies that are valid JSON but don't match the target type are reported in the same format,
+/// with the field being the path of the value that failed to deserialize.
+///
+/// ```rust,no_run
+/// use saas::{
+///     extract::{valid::{Validate, ValidationErrors}, Valid},
+///     routing::post,
+///     Json, Router,
+/// };
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct CreateUser {
+///     email: String,
+///     name: String,
+/// }
+///
+/// impl Validate for CreateUser {
+///     fn validate(&self) -> Result<(), ValidationErrors> {
+///         let mut errors = ValidationErrors::new();
+///         if !self.email.contains('@') {
+///             errors.add("email", "must contain an `@`");
+///         }
+///         if self.name.is_empty() {
+///             errors.add("name", "must not be empty");
+///         }
+///         errors.into_result()
+///     }
+/// }
+///
+/// async fn create_user(Valid(Json(payload)): Valid<Json<CreateUser>>) {
+///     // `payload` has been validated
+/// }
+///
+/// let app = Router::new().route("/users", post(create_user));
+/// # let _: Router = app;
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Valid<E>(pub E);
+
+saas_core::__impl_deref!(Valid);
+
+/// Trait for values that can be validated after extraction.
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+pub trait Validate {
+    /// Validate the value, returning every failing field.
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// Trait for extractors whose extracted value can be validated with [`Valid`].
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+pub trait HasValidate {
+    /// The type that gets validated.
+    type Target: Validate;
+
+    /// Get the value to validate.
+    fn get_validate(&self) -> &Self::Target;
+}
+
+/// Trait for rejections that can be reported as [`ValidationErrors`].
+///
+/// Used by [`Valid`] to report deserialization errors that point at a field in the same format
+/// as validation errors. Rejections that don't map to a field are returned unchanged.
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+pub trait IntoValidationErrors: Sized {
+    /// Convert the rejection into [`ValidationErrors`], or give it back.
+    fn into_validation_errors(self) -> Result<ValidationErrors, Self>;
+}
+
+/// A single failing field.
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Path of the field, for example `user.email` or `items[0]`.
+    pub field: String,
+    /// Why the field is invalid.
+    pub message: String,
+}
+
+/// A list of failing fields.
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    /// Create an empty `ValidationErrors`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an error for `field`.
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Returns `true` if no errors have been added.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Get the errors.
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    /// `Ok(())` if no errors have been added, otherwise `Err(self)`.
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, error) in self.errors.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}: {}", error.field, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        let errors = self
+            .errors
+            .iter()
+            .map(|error| json!({ "field": error.field, "message": error.message }))
+            .collect::<Vec<_>>();
+
+        saas_core::__log_rejection!(
+            rejection_type = ValidationErrors,
+            body_text = self.to_string(),
+            status = StatusCode::UNPROCESSABLE_ENTITY,
+        );
+        let body_text = self.to_string();
+        let mut res = (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "errors": errors })),
+        )
+            .into_response();
+        res.extensions_mut().insert(
+            AnyRejection::__new(StatusCode::UNPROCESSABLE_ENTITY, body_text.clone(), self)
+                .__with_message("ValidationErrors", "Validation failed", Some(body_text)),
+        );
+        res
+    }
+}
+
+/// Rejection used for [`Valid`].
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+#[derive(Debug)]
+pub enum ValidRejection<R> {
+    /// The extracted value failed validation, or couldn't be deserialized into the target type.
+    Invalid(ValidationErrors),
+    /// The inner extractor failed.
+    Inner(R),
+}
+
+impl<R> ValidRejection<R>
+where
+    R: IntoValidationErrors,
+{
+    fn from_inner(rejection: R) -> Self {
+        match rejection.into_validation_errors() {
+            Ok(errors) => Self::Invalid(errors),
+            Err(rejection) => Self::Inner(rejection),
+        }
+    }
+}
+
+impl<R> IntoResponse for ValidRejection<R>
+where
+    R: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        match self {
+            Self::Invalid(errors) => errors.into_response(),
+            Self::Inner(rejection) => rejection.into_response(),
+        }
+    }
+}
+
+impl<R> fmt::Display for ValidRejection<R>
+where
+    R: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(errors) => write!(f, "{}", errors),
+            Self::Inner(rejection) => write!(f, "{}", rejection),
+        }
+    }
+}
+
+impl<R> std::error::Error for ValidRejection<R>
+where
+    R: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Invalid(errors) => Some(errors),
+            Self::Inner(rejection) => Some(rejection),
+        }
+    }
+}
+
+#[async_trait]
+impl<E, S> FromRequest<S> for Valid<E>
+where
+    S: Send + Sync,
+    E: FromRequest<S> + HasValidate,
+    E::Rejection: IntoValidationErrors,
+{
+    type Rejection = ValidRejection<E::Rejection>;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let extracted = E::from_request(req, state)
+            .await
+            .map_err(ValidRejection::from_inner)?;
+        extracted
+            .get_validate()
+            .validate()
+            .map_err(ValidRejection::Invalid)?;
+        Ok(Valid(extracted))
+    }
+}
+
+#[async_trait]
+impl<E, S> FromRequestParts<S> for Valid<E>
+where
+    S: Send + Sync,
+    E: FromRequestParts<S> + HasValidate,
+    E::Rejection: IntoValidationErrors,
+{
+    type Rejection = ValidRejection<E::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let extracted = E::from_request_parts(parts, state)
+            .await
+            .map_err(ValidRejection::from_inner)?;
+        extracted
+            .get_validate()
+            .validate()
+            .map_err(ValidRejection::Invalid)?;
+        Ok(Valid(extracted))
+    }
+}
+
+macro_rules! impl_has_validate {
+    ($($(#[$m:meta])* $ty:ident),* $(,)?) => {
+        $(
+            $(#[$m])*
+            impl<T> HasValidate for $ty<T>
+            where
+                T: Validate,
+            {
+                type Target = T;
+
+                fn get_validate(&self) -> &T {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+impl_has_validate!(
+    Json,
+    Path,
+    #[cfg(feature = "form")]
+    Form,
+    #[cfg(feature = "query")]
+    Query,
+    #[cfg(feature = "query")]
+    NestedQuery,
+);
+
+impl IntoValidationErrors for JsonRejection {
+    fn into_validation_errors(self) -> Result<ValidationErrors, Self> {
+        match self {
+            Self::JsonDataError(JsonDataError(err)) => {
+                let mut errors = ValidationErrors::new();
+                match err
+                    .into_inner()
+                    .downcast::<serde_path_to_error::Error<serde_json::Error>>()
+                {
+                    Ok(err) => errors.add(err.path().to_string(), err.inner().to_string()),
+                    Err(err) => errors.add(".", err.to_string()),
+                }
+                Ok(errors)
+            }
+            rejection => Err(rejection),
+        }
+    }
+}
+
+#[cfg(feature = "query")]
+impl IntoValidationErrors for QueryRejection {
+    fn into_validation_errors(self) -> Result<ValidationErrors, Self> {
+        // Only `NestedQuery` knows the path of the failing field, errors from `Query` are
+        // passed through unchanged.
+        type PathError = serde_path_to_error::Error<QueryDeserializationError>;
+
+        match self {
+            Self::FailedToDeserializeQueryString(FailedToDeserializeQueryString(err)) => {
+                match err.into_inner().downcast::<PathError>() {
+                    Ok(err) => {
+                        let mut errors = ValidationErrors::new();
+                        errors.add(err.path().to_string(), err.inner().to_string());
+                        Ok(errors)
+                    }
+                    Err(err) => Err(FailedToDeserializeQueryString::from_err(err).into()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "form")]
+impl IntoValidationErrors for FormRejection {
+    fn into_validation_errors(self) -> Result<ValidationErrors, Self> {
+        Err(self)
+    }
+}
+
+impl IntoValidationErrors for PathRejection {
+    fn into_validation_errors(self) -> Result<ValidationErrors, Self> {
+        Err(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::Body,
+        routing::{get, post},
+        Router,
+    };
+    use serde::Deserialize;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    #[derive(Deserialize)]
+    struct User {
+        email: String,
+        name: String,
+    }
+
+    impl Validate for User {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            let mut errors = ValidationErrors::new();
+            if !self.email.contains('@') {
+                errors.add("email", "must contain an `@`");
+            }
+            if self.name.is_empty() {
+                errors.add("name", "must not be empty");
+            }
+            errors.into_result()
+        }
+    }
+
+    #[cfg(any(feature = "query", feature = "form"))]
+    #[derive(Deserialize)]
+    struct Search {
+        #[allow(dead_code)]
+        page: Page,
+    }
+
+    #[cfg(any(feature = "query", feature = "form"))]
+    #[derive(Deserialize)]
+    struct Page {
+        #[allow(dead_code)]
+        size: u32,
+    }
+
+    #[cfg(any(feature = "query", feature = "form"))]
+    impl Validate for Search {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            Ok(())
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Id {
+        #[allow(dead_code)]
+        id: u32,
+    }
+
+    impl Validate for Id {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            Ok(())
+        }
+    }
+
+    fn app() -> Router {
+        let app = Router::new()
+            .route("/users", post(|_: Valid<Json<User>>| async {}))
+            .route("/users/:id", get(|_: Valid<Path<Id>>| async {}));
+        form_route(query_route(app))
+    }
+
+    #[cfg(feature = "query")]
+    fn query_route(app: Router) -> Router {
+        app.route("/search", get(|_: Valid<NestedQuery<Search>>| async {}))
+    }
+
+    #[cfg(not(feature = "query"))]
+    fn query_route(app: Router) -> Router {
+        app
+    }
+
+    #[cfg(feature = "form")]
+    fn form_route(app: Router) -> Router {
+        app.route("/form", post(|_: Valid<Form<Search>>| async {}))
+    }
+
+    #[cfg(not(feature = "form"))]
+    fn form_route(app: Router) -> Router {
+        app
+    }
+
+    async fn send(req: http::Request<Body>) -> (StatusCode, String) {
+        let res = app().oneshot(req).await.unwrap();
+        let status = res.status();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn json_request(body: &'static str) -> http::Request<Body> {
+        http::Request::post("/users")
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn get_request(uri: &str) -> http::Request<Body> {
+        http::Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn reports_validation_errors() {
+        let (status, body) = send(json_request(r#"{"email":"alice","name":""}"#)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            serde_json::from_str::<Value>(&body).unwrap(),
+            json!({
+                "errors": [
+                    { "field": "email", "message": "must contain an `@`" },
+                    { "field": "name", "message": "must not be empty" },
+                ]
+            })
+        );
+
+        let valid = r#"{"email":"alice@example.com","name":"Alice"}"#;
+        let (status, _) = send(json_request(valid)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn reports_json_data_errors_as_fields() {
+        let (status, body) = send(json_request(r#"{"email":1,"name":"Alice"}"#)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let body = serde_json::from_str::<Value>(&body).unwrap();
+        let errors = body["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0]["field"], "email");
+        assert!(errors[0]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid type: integer `1`"));
+
+        // syntax errors don't point at a field
+        let (status, body) = send(json_request("{")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.starts_with("Failed to parse the request body as JSON"));
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn reports_nested_query_errors_as_fields() {
+        let (status, body) = send(get_request("/search?page[size]=ten")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let body = serde_json::from_str::<Value>(&body).unwrap();
+        assert_eq!(body["errors"][0]["field"], "page.size");
+
+        let (status, _) = send(get_request("/search?page[size]=10")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn passes_other_rejections_through() {
+        let (status, body) = send(get_request("/users/one")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body,
+            "Invalid URL: Cannot parse `id` with value `\"one\"` to a `u32`"
+        );
+
+        #[cfg(feature = "form")]
+        {
+            let req = http::Request::post("/form").body(Body::from("page=1")).unwrap();
+            let (status, body) = send(req).await;
+            assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            assert_eq!(
+                body,
+                "Form requests must have `Content-Type: application/x-www-form-urlencoded`"
+            );
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use super::{FromRequest, FromRequestParts, Request};
+use crate::response::IntoResponse;
+use async_trait::async_trait;
+use http::request::Parts;
+use std::{
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+/// Extractor that converts the rejection of `E` into `R` through [`From`].
+///
+/// Useful for rendering rejections with an application error type:
+///
+/// ```rust,no_run
+/// use saas::{
+///     extract::{rejection::JsonRejection, WithRejection},
+///     http::StatusCode,
+///     response::{IntoResponse, Response},
+///     routing::post,
+///     Json, Router,
+/// };
+/// use serde_json::{json, Value};
+///
+/// enum ApiError {
+///     Json(JsonRejection),
+/// }
+///
+/// impl From<JsonRejection> for ApiError {
+///     fn from(rejection: JsonRejection) -> Self {
+///         Self::Json(rejection)
+///     }
+/// }
+///
+/// impl IntoResponse for ApiError {
+///     fn into_response(self) -> Response {
+///         match self {
+///             Self::Json(rejection) => (
+///                 rejection.status(),
+///                 Json(json!({ "error": rejection.body_text() })),
+///             )
+///                 .into_response(),
+///         }
+///     }
+/// }
+///
+/// async fn create_user(WithRejection(Json(payload), _): WithRejection<Json<Value>, ApiError>) {}
+///
+/// let app = Router::new().route("/users", post(create_user));
+/// # let _: Router = app;
+/// ```
+///
+/// To change how rejections look for every extractor at once, see
+/// [`Router::map_rejection`](crate::Router::map_rejection).
+pub struct WithRejection<E, R>(pub E, pub PhantomData<R>);
+
+impl<E, R> WithRejection<E, R> {
+    /// Get the extracted value.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+impl<E, R> fmt::Debug for WithRejection<E, R>
+where
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WithRejection").field(&self.0).finish()
+    }
+}
+
+impl<E, R> Clone for WithRejection<E, R>
+where
+    E: Clone,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+}
+
+impl<E, R> Deref for WithRejection<E, R> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E, R> DerefMut for WithRejection<E, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[async_trait]
+impl<E, R, S> FromRequest<S> for WithRejection<E, R>
+where
+    S: Send + Sync,
+    E: FromRequest<S>,
+    R: From<E::Rejection> + IntoResponse,
+{
+    type Rejection = R;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let extractor = E::from_request(req, state).await?;
+        Ok(Self(extractor, PhantomData))
+    }
+}
+
+#[async_trait]
+impl<E, R, S> FromRequestParts<S> for WithRejection<E, R>
+where
+    S: Send + Sync,
+    E: FromRequestParts<S>,
+    R: From<E::Rejection> + IntoResponse,
+{
+    type Rejection = R;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let extractor = E::from_request_parts(parts, state).await?;
+        Ok(Self(extractor, PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::rejection::ExtensionRejection;
+    use crate::Extension;
+    use http::StatusCode;
+
+    #[derive(Debug)]
+    struct TestError(StatusCode);
+
+    impl From<ExtensionRejection> for TestError {
+        fn from(rejection: ExtensionRejection) -> Self {
+            Self(rejection.status())
+        }
+    }
+
+    impl IntoResponse for TestError {
+        fn into_response(self) -> crate::response::Response {
+            self.0.into_response()
+        }
+    }
+
+    #[tokio::test]
+    async fn converts_rejection() {
+        let (mut parts, _) = http::Request::new(()).into_parts();
+        let err = WithRejection::<Extension<String>, TestError>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -26,7 +26,7 @@ where
         match req.extract().await {
             Ok(RawForm(bytes)) => {
                 let value = 
-                    serde_urlencoded::from_bytes(&bytes).map_err(|err| -> FormRejection {
+                    decode(&bytes).map_err(|err| -> FormRejection {
                         if is_get_or_head {
                             FailedToDeserializeForm::from_err(err).into()
                         }else {
@@ -49,7 +49,7 @@ where
     T: Serialize,
 {
     fn into_response(self) -> saas_core::response::Response {
-        match serde_urlencoded::to_string(&self.0) {
+        match encode(&self.0) {
             Ok(body) => (
                 [(CONTENT_TYPE, mime::APPLICATION_WWW_FORM_URLENCODED.as_ref())],
                 body,
@@ -59,4 +59,20 @@ where
     }
 }
 
-saas_core::__impl_deref!(Form);
\ No newline at end of file
+saas_core::__impl_deref!(Form);
+
+/// Deserialize an `application/x-www-form-urlencoded` body, shared with `FormCodec`.
+pub(crate) fn decode<T>(bytes: &[u8]) -> Result<T, serde_urlencoded::de::Error>
+where
+    T: DeserializeOwned,
+{
+    serde_urlencoded::from_bytes(bytes)
+}
+
+/// Serialize an `application/x-www-form-urlencoded` body, shared with `FormCodec`.
+pub(crate) fn encode<T>(value: &T) -> Result<String, serde_urlencoded::ser::Error>
+where
+    T: Serialize + ?Sized,
+{
+    serde_urlencoded::to_string(value)
+}
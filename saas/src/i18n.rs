@@ -0,0 +1,706 @@
+//! Locale negotiation and translated messages.
+//!
+//! A [`Catalog`] holds [Fluent] messages for every supported locale and is added to the state.
+//! [`Locale`] picks the best supported locale for a request and [`Messages`] formats messages
+//! in that locale:
+//!
+//! ```rust,no_run
+//! use saas::{
+//!     i18n::{Catalog, FluentArgs, Messages},
+//!     response::Html,
+//!     routing::get,
+//!     Router,
+//! };
+//!
+//! // `locales/en.ftl`, `locales/de.ftl`, ...
+//! let catalog = Catalog::from_dir("locales", "en").unwrap();
+//!
+//! async fn dashboard(messages: Messages) -> Html<String> {
+//!     let mut args = FluentArgs::new();
+//!     args.set("name", "Alice");
+//!
+//!     Html(format!(
+//!         "<html lang=\"{}\"><h1>{}</h1></html>",
+//!         messages.locale(),
+//!         messages.format("welcome", &args),
+//!     ))
+//! }
+//!
+//! let app = Router::new()
+//!     .route("/", get(dashboard))
+//!     .with_state(catalog);
+//! # let _: Router = app;
+//! ```
+//!
+//! # Picking the locale
+//!
+//! The locale is taken from, in order:
+//!
+//! 1. The `lang` query parameter, such as `?lang=de`.
+//! 2. The `lang` cookie.
+//! 3. The `Accept-Language` header, by q-value. A tag matches a supported locale with the
+//!    same language if there is no exact match, so `de-CH` picks `de`.
+//! 4. The default locale of the catalog.
+//!
+//! Values that aren't supported locales are ignored. The query parameter and cookie names can
+//! be changed with [`Catalog::query_param`] and [`Catalog::cookie_name`].
+//!
+//! # Translating rejections
+//!
+//! [`TranslateRejectionsLayer`] replaces the body of responses from the built-in rejections
+//! with the message `rejection-<name>` of the catalog, for example
+//! `rejection-MissingJsonContentType`. The error that caused the rejection, if any, is
+//! available as `$detail`:
+//!
+//! ```text
+//! rejection-MissingJsonContentType = Die Anfrage muss `Content-Type: application/json` haben
+//! rejection-JsonSyntaxError = Ungültiges JSON: { $detail }
+//! ```
+//!
+//! Rejections without a message keep their English body.
+//!
+//! [Fluent]: https://projectfluent.org
+
+use crate::extract::{rejection::AnyRejection, FromRef, FromRequestParts};
+use async_trait::async_trait;
+use fluent_bundle::{concurrent::FluentBundle, FluentResource};
+use futures_util::ready;
+use http::{
+    header::{self, HeaderMap, HeaderValue},
+    request::Parts,
+    Request, Uri,
+};
+use pin_project_lite::pin_project;
+use saas_core::{body::Body, response::Response};
+use std::{
+    borrow::Cow,
+    convert::Infallible,
+    error::Error,
+    fmt, fs, io,
+    future::Future,
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+#[doc(no_inline)]
+pub use fluent_bundle::{FluentArgs, FluentValue};
+#[doc(no_inline)]
+pub use unic_langid::LanguageIdentifier;
+
+/// Fluent messages for a set of locales.
+///
+/// Cheap to clone. See the [module docs](self) for more details.
+#[derive(Clone)]
+pub struct Catalog {
+    locales: Arc<Locales>,
+    query_param: Cow<'static, str>,
+    cookie_name: Cow<'static, str>,
+}
+
+struct Locales {
+    bundles: Vec<(LanguageIdentifier, FluentBundle<FluentResource>)>,
+    default: usize,
+}
+
+impl Catalog {
+    /// Load the `.ftl` files in `dir`.
+    ///
+    /// Every file is named after its locale, such as `en-US.ftl`. Messages can also be split
+    /// over several files in a directory named after the locale, such as `en-US/errors.ftl`.
+    pub fn from_dir(dir: impl AsRef<Path>, default: &str) -> Result<Self, CatalogError> {
+        let mut sources = Vec::new();
+
+        for (path, is_dir) in sorted_entries(dir.as_ref())? {
+            let locale = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(locale) => locale.to_owned(),
+                None => continue,
+            };
+
+            if is_dir {
+                for (path, is_dir) in sorted_entries(&path)? {
+                    if !is_dir && is_ftl(&path) {
+                        sources.push((locale.clone(), read(&path)?));
+                    }
+                }
+            } else if is_ftl(&path) {
+                sources.push((locale, read(&path)?));
+            }
+        }
+
+        Self::from_sources(default, sources)
+    }
+
+    /// Build a catalog from `(locale, source)` pairs of Fluent sources.
+    ///
+    /// A locale can appear several times to split its messages over several sources.
+    pub fn from_sources<I, L, T>(default: &str, sources: I) -> Result<Self, CatalogError>
+    where
+        I: IntoIterator<Item = (L, T)>,
+        L: AsRef<str>,
+        T: Into<String>,
+    {
+        let mut bundles: Vec<(LanguageIdentifier, FluentBundle<FluentResource>)> = Vec::new();
+
+        for (locale, source) in sources {
+            let locale = parse_locale(locale.as_ref())?;
+            let resource = FluentResource::try_new(source.into()).map_err(|(_, errors)| {
+                CatalogError::invalid_source(&locale, errors.iter().map(ToString::to_string))
+            })?;
+
+            let bundle = match bundles.iter().position(|(existing, _)| *existing == locale) {
+                Some(idx) => &mut bundles[idx].1,
+                None => {
+                    let mut bundle = FluentBundle::new_concurrent(vec![locale.clone()]);
+                    // the unicode isolation marks would end up in HTML and plain text bodies
+                    bundle.set_use_isolating(false);
+                    bundles.push((locale.clone(), bundle));
+                    &mut bundles.last_mut().unwrap().1
+                }
+            };
+
+            bundle.add_resource(resource).map_err(|errors| {
+                CatalogError::invalid_source(&locale, errors.iter().map(ToString::to_string))
+            })?;
+        }
+
+        let default_locale = parse_locale(default)?;
+        let default = bundles
+            .iter()
+            .position(|(locale, _)| *locale == default_locale)
+            .ok_or_else(|| CatalogError::new(ErrorKind::MissingDefault(default_locale)))?;
+
+        Ok(Self {
+            locales: Arc::new(Locales { bundles, default }),
+            query_param: Cow::Borrowed("lang"),
+            cookie_name: Cow::Borrowed("lang"),
+        })
+    }
+
+    /// Set the name of the query parameter that overrides the locale.
+    ///
+    /// Defaults to `lang`.
+    pub fn query_param(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.query_param = name.into();
+        self
+    }
+
+    /// Set the name of the cookie that overrides the locale.
+    ///
+    /// Defaults to `lang`.
+    pub fn cookie_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// The supported locales, in the order they were loaded.
+    pub fn locales(&self) -> impl Iterator<Item = &LanguageIdentifier> {
+        self.locales.bundles.iter().map(|(locale, _)| locale)
+    }
+
+    /// The default locale.
+    pub fn default_locale(&self) -> &LanguageIdentifier {
+        self.locale(self.locales.default)
+    }
+
+    /// Format the message `id` in `locale`.
+    ///
+    /// Falls back to the default locale if `locale` isn't supported or doesn't have the
+    /// message. Returns `None` if the default locale doesn't have it either.
+    pub fn message(
+        &self,
+        locale: &LanguageIdentifier,
+        id: &str,
+        args: Option<&FluentArgs<'_>>,
+    ) -> Option<String> {
+        let idx = self
+            .locales
+            .bundles
+            .iter()
+            .position(|(supported, _)| supported == locale)
+            .unwrap_or(self.locales.default);
+        self.format(idx, id, args)
+    }
+
+    fn locale(&self, idx: usize) -> &LanguageIdentifier {
+        &self.locales.bundles[idx].0
+    }
+
+    fn format(&self, idx: usize, id: &str, args: Option<&FluentArgs<'_>>) -> Option<String> {
+        [idx, self.locales.default].iter().find_map(|&idx| {
+            let bundle = &self.locales.bundles[idx].1;
+            let pattern = bundle.get_message(id)?.value()?;
+            // formatting errors, such as missing arguments, are rendered inline by fluent
+            let mut errors = Vec::new();
+            Some(bundle.format_pattern(pattern, args, &mut errors).into_owned())
+        })
+    }
+
+    fn negotiate(&self, uri: &Uri, headers: &HeaderMap) -> usize {
+        let query_override = uri.query().and_then(|query| {
+            form_urlencoded::parse(query.as_bytes())
+                .find(|(name, _)| *name == self.query_param)
+                .and_then(|(_, value)| self.find(&value))
+        });
+
+        let cookie_override = || {
+            headers
+                .get_all(header::COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(';'))
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(name, _)| name.trim() == self.cookie_name)
+                .and_then(|(_, value)| self.find(value.trim().trim_matches('"')))
+        };
+
+        query_override
+            .or_else(cookie_override)
+            .or_else(|| self.accept_language(headers.get(header::ACCEPT_LANGUAGE)?))
+            .unwrap_or(self.locales.default)
+    }
+
+    fn accept_language(&self, value: &HeaderValue) -> Option<usize> {
+        let mut ranges = value
+            .to_str()
+            .ok()?
+            .split(',')
+            .filter_map(|range| {
+                let mut params = range.split(';');
+                let tag = params.next()?.trim();
+                let q = params
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (!tag.is_empty() && q > 0.0).then_some((tag, q))
+            })
+            .collect::<Vec<_>>();
+        // stable, so ranges with the same q-value keep their order
+        ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        ranges.into_iter().find_map(|(tag, _)| {
+            if tag == "*" {
+                return Some(self.locales.default);
+            }
+
+            let requested = tag.parse::<LanguageIdentifier>().ok()?;
+            let bundles = &self.locales.bundles;
+            bundles
+                .iter()
+                .position(|(locale, _)| *locale == requested)
+                .or_else(|| {
+                    bundles
+                        .iter()
+                        .position(|(locale, _)| locale.language == requested.language)
+                })
+        })
+    }
+
+    fn find(&self, locale: &str) -> Option<usize> {
+        let locale = locale.parse::<LanguageIdentifier>().ok()?;
+        self.locales
+            .bundles
+            .iter()
+            .position(|(supported, _)| *supported == locale)
+    }
+}
+
+impl fmt::Debug for Catalog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Catalog")
+            .field("locales", &self.locales().collect::<Vec<_>>())
+            .field("default_locale", self.default_locale())
+            .field("query_param", &self.query_param)
+            .field("cookie_name", &self.cookie_name)
+            .finish()
+    }
+}
+
+fn parse_locale(locale: &str) -> Result<LanguageIdentifier, CatalogError> {
+    locale
+        .parse()
+        .map_err(|_| CatalogError::new(ErrorKind::InvalidLocale(locale.to_owned())))
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<(std::path::PathBuf, bool)>, CatalogError> {
+    let io_error = |err| CatalogError::new(ErrorKind::Io(dir.to_owned(), err));
+
+    let mut entries = fs::read_dir(dir)
+        .map_err(io_error)?
+        .map(|entry| {
+            let entry = entry?;
+            Ok((entry.path(), entry.file_type()?.is_dir()))
+        })
+        .collect::<io::Result<Vec<_>>>()
+        .map_err(io_error)?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn is_ftl(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "ftl")
+}
+
+fn read(path: &Path) -> Result<String, CatalogError> {
+    fs::read_to_string(path).map_err(|err| CatalogError::new(ErrorKind::Io(path.to_owned(), err)))
+}
+
+/// Error returned when a [`Catalog`] can't be built.
+#[derive(Debug)]
+pub struct CatalogError {
+    kind: ErrorKind,
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    Io(std::path::PathBuf, io::Error),
+    InvalidLocale(String),
+    InvalidSource(LanguageIdentifier, Vec<String>),
+    MissingDefault(LanguageIdentifier),
+}
+
+impl CatalogError {
+    fn new(kind: ErrorKind) -> Self {
+        Self { kind }
+    }
+
+    fn invalid_source(locale: &LanguageIdentifier, errors: impl Iterator<Item = String>) -> Self {
+        Self::new(ErrorKind::InvalidSource(locale.clone(), errors.collect()))
+    }
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::Io(path, err) => write!(f, "failed to read `{}`: {}", path.display(), err),
+            ErrorKind::InvalidLocale(locale) => write!(f, "invalid locale `{}`", locale),
+            ErrorKind::InvalidSource(locale, errors) => {
+                write!(f, "invalid messages for `{}`: {}", locale, errors.join(", "))
+            }
+            ErrorKind::MissingDefault(locale) => {
+                write!(f, "no messages for the default locale `{}`", locale)
+            }
+        }
+    }
+}
+
+impl Error for CatalogError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Extractor for the best supported locale of the request.
+///
+/// Requires a [`Catalog`] in the state. See the [module docs](self#picking-the-locale) for how
+/// the locale is picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(pub LanguageIdentifier);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Locale
+where
+    Catalog: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let catalog = Catalog::from_ref(state);
+        let idx = catalog.negotiate(&parts.uri, &parts.headers);
+        Ok(Self(catalog.locale(idx).clone()))
+    }
+}
+
+saas_core::__impl_deref!(Locale: LanguageIdentifier);
+
+/// Extractor that formats messages in the [`Locale`] of the request.
+///
+/// Messages missing from the locale fall back to the default locale of the [`Catalog`]. It's
+/// cheap to clone, so it can be passed on to templates.
+#[derive(Clone)]
+pub struct Messages {
+    catalog: Catalog,
+    locale: usize,
+}
+
+impl Messages {
+    /// The locale messages are formatted in.
+    pub fn locale(&self) -> &LanguageIdentifier {
+        self.catalog.locale(self.locale)
+    }
+
+    /// Format the message `id`.
+    ///
+    /// Returns `id` itself if the message doesn't exist.
+    pub fn get(&self, id: &str) -> String {
+        self.catalog
+            .format(self.locale, id, None)
+            .unwrap_or_else(|| id.to_owned())
+    }
+
+    /// Format the message `id` with arguments.
+    ///
+    /// Returns `id` itself if the message doesn't exist.
+    pub fn format(&self, id: &str, args: &FluentArgs<'_>) -> String {
+        self.catalog
+            .format(self.locale, id, Some(args))
+            .unwrap_or_else(|| id.to_owned())
+    }
+}
+
+impl fmt::Debug for Messages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Messages")
+            .field("locale", self.locale())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Messages
+where
+    Catalog: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let catalog = Catalog::from_ref(state);
+        let locale = catalog.negotiate(&parts.uri, &parts.headers);
+        Ok(Self { catalog, locale })
+    }
+}
+
+/// Layer that translates the bodies of rejection responses.
+///
+/// See the [module docs](self#translating-rejections) for more details.
+#[derive(Debug, Clone)]
+pub struct TranslateRejectionsLayer {
+    catalog: Catalog,
+}
+
+impl TranslateRejectionsLayer {
+    /// Create a new `TranslateRejectionsLayer` using messages from `catalog`.
+    pub fn new(catalog: Catalog) -> Self {
+        Self { catalog }
+    }
+}
+
+impl<S> Layer<S> for TranslateRejectionsLayer {
+    type Service = TranslateRejections<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TranslateRejections {
+            inner,
+            catalog: self.catalog.clone(),
+        }
+    }
+}
+
+/// Middleware that translates the bodies of rejection responses.
+///
+/// Created with [`TranslateRejectionsLayer`].
+#[derive(Debug, Clone)]
+pub struct TranslateRejections<S> {
+    inner: S,
+    catalog: Catalog,
+}
+
+impl<B, S> Service<Request<B>> for TranslateRejections<S>
+where
+    S: Service<Request<B>, Response = Response>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let locale = self.catalog.negotiate(req.uri(), req.headers());
+        ResponseFuture {
+            inner: self.inner.call(req),
+            catalog: Some(self.catalog.clone()),
+            locale,
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`TranslateRejections`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        catalog: Option<Catalog>,
+        locale: usize,
+    }
+}
+
+impl<F> fmt::Debug for ResponseFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture")
+            .field("catalog", &self.catalog)
+            .field("locale", &self.locale)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response, E>>,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut res = ready!(this.inner.poll(cx))?;
+
+        let catalog = this.catalog.take().expect("future polled after completion");
+        if let Some(rejection) = res.extensions().get::<AnyRejection>() {
+            let mut args = FluentArgs::new();
+            if let Some(detail) = rejection.detail() {
+                args.set("detail", detail.to_owned());
+            }
+
+            let id = format!("rejection-{}", rejection.name());
+            if let Some(text) = catalog.format(*this.locale, &id, Some(&args)) {
+                let headers = res.headers_mut();
+                headers.remove(header::CONTENT_LENGTH);
+                headers.insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(mime::TEXT_PLAIN_UTF_8.as_ref()),
+                );
+                if let Ok(locale) = HeaderValue::try_from(catalog.locale(*this.locale).to_string())
+                {
+                    headers.insert(header::CONTENT_LANGUAGE, locale);
+                }
+                *res.body_mut() = Body::from(text);
+            }
+        }
+
+        Poll::Ready(Ok(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        Catalog::from_sources(
+            "en",
+            [
+                ("en", "hello = Hello, { $name }!\nbye = Bye"),
+                ("de", "hello = Hallo, { $name }!"),
+                ("pt-BR", "hello = Olá, { $name }!"),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn negotiate(uri: &str, headers: &[(&str, &str)]) -> String {
+        let req = headers
+            .iter()
+            .fold(Request::builder().uri(uri), |req, (name, value)| {
+                req.header(*name, *value)
+            })
+            .body(())
+            .unwrap();
+        let catalog = catalog();
+        let idx = catalog.negotiate(req.uri(), req.headers());
+        catalog.locale(idx).to_string()
+    }
+
+    #[test]
+    fn negotiates_locale() {
+        assert_eq!(negotiate("/", &[]), "en");
+        assert_eq!(
+            negotiate("/", &[("accept-language", "fr, pt;q=0.8, de;q=0.9")]),
+            "de"
+        );
+        assert_eq!(negotiate("/", &[("accept-language", "pt-PT")]), "pt-BR");
+        assert_eq!(
+            negotiate("/", &[("accept-language", "de"), ("cookie", "theme=dark; lang=pt-BR")]),
+            "pt-BR"
+        );
+        assert_eq!(
+            negotiate("/?lang=de", &[("cookie", "lang=pt-BR")]),
+            "de"
+        );
+        assert_eq!(negotiate("/?lang=xx", &[]), "en");
+    }
+
+    #[test]
+    fn falls_back_to_default_locale() {
+        let catalog = catalog();
+        let mut args = FluentArgs::new();
+        args.set("name", "Ana");
+
+        let messages = Messages {
+            locale: catalog.find("pt-BR").unwrap(),
+            catalog,
+        };
+        assert_eq!(messages.format("hello", &args), "Olá, Ana!");
+        assert_eq!(messages.get("bye"), "Bye");
+        assert_eq!(messages.get("missing"), "missing");
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn translates_rejected_requests() {
+        use crate::{extract::Path, routing::post, Json, Router};
+        use serde_json::Value;
+        use tower::ServiceExt;
+
+        let catalog = Catalog::from_sources(
+            "en",
+            [
+                ("en", "hello = Hello"),
+                (
+                    "de",
+                    "rejection-MissingJsonContentType = Kein JSON\n\
+                     rejection-FailedToDeserializePathParams = Ungültige URL: { $detail }",
+                ),
+            ],
+        )
+        .unwrap();
+        let app = Router::new()
+            .route("/users/:id", post(|_: Path<u32>, _: Json<Value>| async {}))
+            .layer(TranslateRejectionsLayer::new(catalog));
+
+        let send = |uri: &'static str, lang: &'static str| {
+            let req = Request::post(uri)
+                .header(header::ACCEPT_LANGUAGE, lang)
+                .body(Body::empty())
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let res = app.oneshot(req).await.unwrap();
+                let language = res.headers().get(header::CONTENT_LANGUAGE).cloned();
+                let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                (language, body)
+            }
+        };
+
+        let (language, body) = send("/users/1", "de").await;
+        assert_eq!(language.unwrap(), "de");
+        assert_eq!(body, "Kein JSON");
+
+        let (_, body) = send("/users/one", "de").await;
+        assert_eq!(body, "Ungültige URL: Cannot parse `\"one\"` to a `u32`");
+
+        let (language, body) = send("/users/1", "en").await;
+        assert!(language.is_none());
+        assert_eq!(body, "Expected request with `Content-Type: application/json`");
+    }
+}
@@ -1,5 +1,6 @@
 use crate::extract::Request;
-use crate::extract::{rejection::*, FromRequest};
+use crate::extract::{rejection::*, DefaultBodyLimit, FromRequest};
+use crate::Extension;
 use async_trait::async_trait;
 use saas_core::response::{IntoResponse, Response};
 use bytes::{BufMut, Bytes, BytesMut};
@@ -7,7 +8,12 @@ use http::{
     header::{self, HeaderMap, HeaderValue},
     StatusCode,
 };
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    Serialize,
+};
+use std::{cell::Cell, collections::HashSet, fmt};
+use tower_layer::Layer;
 
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -21,38 +27,60 @@ where
 {
     type Rejection = JsonRejection;
 
-    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
-        if json_content_type(req.headers()) {
-            let bytes = Bytes::from_request(req, state).await?;
-            let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
-
-            let value = match serde_path_to_error::deserialize(deserializer) {
-                Ok(value) => value,
-                Err(err) => {
-                    let rejection = match err.inner().classify() {
-                        serde_json::error::Category::Data => JsonDataError::from_err(err).into(),
-                        serde_json::error::Category::Syntax | serde_json::error::Category::Eof => {
-                            JsonSyntaxError::from_err(err).into()
-                        }
-                        serde_json::error::Category::Io => {
-                            if cfg!(debug_assertions) {
-                                unreachable!()
-                            }else {
-                                JsonSyntaxError::from_err(err).into()
-                            }
-                        }
-                    };
-                    return Err(rejection);
-                }
-            };
+    async fn from_request(mut req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let config = req
+            .extensions()
+            .get::<JsonConfig>()
+            .copied()
+            .unwrap_or_default();
 
-            Ok(Json(value))
+        if config.accepts(req.headers()) {
+            if let Some(limit) = config.limit {
+                DefaultBodyLimit::max(limit).__insert_into(req.extensions_mut());
+            }
+            let bytes = Bytes::from_request(req, state).await?;
+            config.check(&bytes)?;
+            Self::from_bytes(&bytes)
         }else {
             Err(MissingJsonContentType.into())
         }
     }
 }
 
+impl<T> Json<T>
+where
+    T: DeserializeOwned,
+{
+    /// Construct a `Json<T>` from a byte slice. Most users should prefer to use the `FromRequest`
+    /// impl but special cases may require first extracting a `Request` into `Bytes` then
+    /// optionally constructing a `Json<T>`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, JsonRejection> {
+        let deserializer = &mut serde_json::Deserializer::from_slice(bytes);
+
+        let value = match serde_path_to_error::deserialize(deserializer) {
+            Ok(value) => value,
+            Err(err) => {
+                let rejection = match err.inner().classify() {
+                    serde_json::error::Category::Data => JsonDataError::from_err(err).into(),
+                    serde_json::error::Category::Syntax | serde_json::error::Category::Eof => {
+                        JsonSyntaxError::from_err(err).into()
+                    }
+                    serde_json::error::Category::Io => {
+                        if cfg!(debug_assertions) {
+                            unreachable!()
+                        }else {
+                            JsonSyntaxError::from_err(err).into()
+                        }
+                    }
+                };
+                return Err(rejection);
+            }
+        };
+
+        Ok(Json(value))
+    }
+}
+
 fn json_content_type(headers: &HeaderMap) -> bool {
     let content_type = if let Some(content_type) = headers.get(header::CONTENT_TYPE) {
         content_type
@@ -73,27 +101,242 @@ fn json_content_type(headers: &HeaderMap) -> bool {
     };
 
     let is_json_content_type = mime.type_() == "application"
-        && (mime.subtype() == "json" || mime.suffix().map_or(false, |name| name == "json"));
+        && (mime.subtype() == "json" || mime.suffix().is_some_and(|name| name == "json"));
 
     is_json_content_type
 }
 
 saas_core::__impl_deref!(Json);
 
+/// Configuration for the [`Json`] extractor.
+///
+/// Added as a layer, for the whole router or a single route. Without it `Json` requires
+/// `Content-Type: application/json` and uses the [`DefaultBodyLimit`].
+///
+/// ```rust,no_run
+/// use saas::{routing::post, Json, JsonConfig, Router};
+/// use serde_json::Value;
+///
+/// async fn webhook(Json(payload): Json<Value>) {
+///     // ...
+/// }
+///
+/// let app = Router::new().route(
+///     "/webhook",
+///     post(webhook).layer(
+///         JsonConfig::new()
+///             .allow_missing_content_type()
+///             .limit(64 * 1024)
+///             .max_depth(16)
+///             .reject_duplicate_keys(),
+///     ),
+/// );
+/// # let _: Router = app;
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[must_use]
+pub struct JsonConfig {
+    allow_missing_content_type: bool,
+    any_content_type: bool,
+    limit: Option<usize>,
+    max_depth: Option<usize>,
+    reject_duplicate_keys: bool,
+}
+
+impl JsonConfig {
+    /// Create a new `JsonConfig` with the default behavior.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept requests without a `Content-Type`.
+    pub fn allow_missing_content_type(mut self) -> Self {
+        self.allow_missing_content_type = true;
+        self
+    }
+
+    /// Accept requests with any `Content-Type`, such as `text/plain`.
+    ///
+    /// Requests without a `Content-Type` are still rejected unless
+    /// [`allow_missing_content_type`](Self::allow_missing_content_type) is also set.
+    pub fn any_content_type(mut self) -> Self {
+        self.any_content_type = true;
+        self
+    }
+
+    /// Set the body size limit in bytes, overriding the [`DefaultBodyLimit`].
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Reject bodies with objects and arrays nested deeper than `max_depth`.
+    ///
+    /// A top level object or array has a depth of 1. `serde_json` always rejects bodies nested
+    /// deeper than 128, regardless of this setting.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Reject bodies with objects that contain the same key more than once.
+    ///
+    /// By default the last value wins for maps and deserializing a struct fails with a
+    /// [`JsonDataError`].
+    pub fn reject_duplicate_keys(mut self) -> Self {
+        self.reject_duplicate_keys = true;
+        self
+    }
+
+    fn accepts(&self, headers: &HeaderMap) -> bool {
+        if !headers.contains_key(header::CONTENT_TYPE) {
+            self.allow_missing_content_type
+        } else {
+            self.any_content_type || json_content_type(headers)
+        }
+    }
+
+    fn check(&self, bytes: &[u8]) -> Result<(), JsonRejection> {
+        if self.max_depth.is_none() && !self.reject_duplicate_keys {
+            return Ok(());
+        }
+
+        let violation = Cell::new(None);
+        let check = Check {
+            config: self,
+            depth: 0,
+            violation: &violation,
+        };
+        let deserializer = &mut serde_json::Deserializer::from_slice(bytes);
+
+        match (check.deserialize(deserializer), violation.get()) {
+            (Err(_), Some(Violation::TooDeep)) => Err(JsonNestingTooDeep.into()),
+            (Err(err), Some(Violation::DuplicateKey)) => Err(DuplicateJsonKey::from_err(err).into()),
+            // syntax errors are reported when deserializing the target type
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<S> Layer<S> for JsonConfig {
+    type Service = <Extension<Self> as Layer<S>>::Service;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Extension(*self).layer(inner)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Violation {
+    TooDeep,
+    DuplicateKey,
+}
+
+// walks the document to check the limits of `JsonConfig`, without building any values
+#[derive(Clone, Copy)]
+struct Check<'a> {
+    config: &'a JsonConfig,
+    depth: usize,
+    violation: &'a Cell<Option<Violation>>,
+}
+
+impl Check<'_> {
+    fn nested<E>(self) -> Result<Self, E>
+    where
+        E: de::Error,
+    {
+        let depth = self.depth + 1;
+        if self.config.max_depth.is_some_and(|max_depth| depth > max_depth) {
+            self.violation.set(Some(Violation::TooDeep));
+            return Err(E::custom("nested too deeply"));
+        }
+        Ok(Self { depth, ..self })
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Check<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Check<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let nested = self.nested()?;
+        while seq.next_element_seed(nested)?.is_some() {}
+        Ok(())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let nested = self.nested()?;
+        let mut keys = HashSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if self.config.reject_duplicate_keys && keys.contains(&key) {
+                self.violation.set(Some(Violation::DuplicateKey));
+                return Err(de::Error::custom(format_args!("duplicate key `{}`", key)));
+            }
+            map.next_value_seed(nested)?;
+            if self.config.reject_duplicate_keys {
+                keys.insert(key);
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<T> IntoResponse for Json<T>
 where
     T: Serialize,
 {
     fn into_response(self) -> Response {
-        let mut buf = BytesMut::with_capacity(128).writer();
-
-        match serde_json::to_writer(&mut buf, &self.0) {
-            Ok(()) => (
+        match encode(&self.0) {
+            Ok(buf) => (
                 [(
                     header::CONTENT_TYPE,
                     HeaderValue::from_static(mime::APPLICATION_JSON.as_ref()),
                 )],
-                buf.into_inner().freeze(),
+                buf.freeze(),
             ).into_response(),
 
             Err(err) => (
@@ -106,4 +349,90 @@ where
             ).into_response(),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Serialize `value` the same way as [`Json`] responses, for other responses with JSON bodies.
+pub(crate) fn encode<T>(value: &T) -> Result<BytesMut, serde_json::Error>
+where
+    T: Serialize + ?Sized,
+{
+    let mut buf = BytesMut::with_capacity(128).writer();
+    serde_json::to_writer(&mut buf, value)?;
+    Ok(buf.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(config: JsonConfig, body: &str) -> Result<(), JsonRejection> {
+        config.check(body.as_bytes())
+    }
+
+    #[test]
+    fn checks_depth_and_duplicate_keys() {
+        let config = JsonConfig::new().max_depth(2).reject_duplicate_keys();
+
+        assert!(check(config, r#"{"a": [1, 2], "b": {"c": null}}"#).is_ok());
+        assert!(matches!(
+            check(config, r#"{"a": [[1]]}"#),
+            Err(JsonRejection::JsonNestingTooDeep(_))
+        ));
+        let rejection = check(config, r#"{"a": 1, "b": {"a": 2, "a": 3}}"#).unwrap_err();
+        assert!(matches!(rejection, JsonRejection::DuplicateJsonKey(_)));
+        assert!(rejection.body_text().contains("duplicate key `a`"));
+
+        // left for the actual deserialization to report
+        assert!(check(config, "{").is_ok());
+        assert!(check(JsonConfig::new(), r#"{"a": 1, "a": 2}"#).is_ok());
+    }
+
+    #[test]
+    fn accepts_content_types() {
+        let mut headers = HeaderMap::new();
+        assert!(!JsonConfig::new().accepts(&headers));
+        assert!(JsonConfig::new().allow_missing_content_type().accepts(&headers));
+
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        assert!(!JsonConfig::new().accepts(&headers));
+        assert!(JsonConfig::new().any_content_type().accepts(&headers));
+    }
+
+    #[tokio::test]
+    async fn config_layer_rejects_requests() {
+        use crate::{body::Body, routing::post, Router};
+        use serde_json::Value;
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route("/", post(|_: Json<Value>| async {}))
+            .layer(JsonConfig::new().limit(32).max_depth(2).reject_duplicate_keys());
+
+        let send = |body: &'static str| {
+            let req = http::Request::post("/")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            app.clone().oneshot(req)
+        };
+
+        let res = send(r#"{"a": [1], "b": 2}"#).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = send(r#"{"a": "a string longer than the limit"}"#).await.unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(
+            body,
+            "Request body is too large: length limit of 32 bytes exceeded"
+        );
+
+        let res = send(r#"{"a": [[1]]}"#).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "JSON body is nested too deeply");
+
+        let res = send(r#"{"a": 1, "a": 2}"#).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -0,0 +1,429 @@
+//! Newline delimited JSON, also known as JSON Lines.
+//!
+//! See [`JsonLines`] for more details.
+
+use crate::{
+    extract::{
+        body_stream::{BodyStream, BodyStreamError},
+        rejection::{AnyRejection, JsonRejection},
+        FromRequest, Request,
+    },
+    BoxError, Json,
+};
+use async_trait::async_trait;
+use bytes::{BufMut, BytesMut};
+use futures_util::stream::{Stream, StreamExt, TryStream, TryStreamExt};
+use http::header::{self, HeaderValue};
+use private::{Lines, Mode};
+use saas_core::{
+    body::Body,
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    convert::Infallible,
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A stream of newline delimited JSON ([NDJSON]).
+///
+/// This can be used both as an extractor and as a response.
+///
+/// # As extractor
+///
+/// Lines are deserialized as the body arrives, without buffering the whole body. Each line
+/// is deserialized the same way as [`Json`], blank lines are ignored. What happens when a
+/// line can't be deserialized is controlled with [`JsonLines::on_error`].
+///
+/// The body is read with [`BodyStream`] so [`DefaultBodyLimit`] and
+/// [`BodyReadTimeout`] apply to the whole stream. Large batches will usually require
+/// raising the default limit.
+///
+/// ```rust,no_run
+/// use saas::{
+///     extract::DefaultBodyLimit,
+///     json_lines::LineErrorPolicy,
+///     routing::post,
+///     JsonLines, Router,
+/// };
+/// use futures_util::stream::StreamExt;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Event {
+///     kind: String,
+/// }
+///
+/// async fn ingest(events: JsonLines<Event>) {
+///     let mut events = events.on_error(LineErrorPolicy::Skip);
+///     while let Some(event) = events.next().await {
+///         // ...
+///     }
+/// }
+///
+/// let app = Router::new()
+///     .route("/events", post(ingest))
+///     .layer(DefaultBodyLimit::max(64 * 1024 * 1024));
+/// # let _: Router = app;
+/// ```
+///
+/// # As response
+///
+/// Items are serialized lazily as the stream yields them, with
+/// `Content-Type: application/x-ndjson`. If the stream yields an error, or an item fails to
+/// serialize, the response body is aborted.
+///
+/// ```rust
+/// use saas::{json_lines::AsResponse, routing::get, JsonLines, Router};
+/// use futures_util::stream::{self, Stream};
+/// use serde::Serialize;
+/// use std::convert::Infallible;
+///
+/// #[derive(Serialize)]
+/// struct Event {
+///     id: u64,
+/// }
+///
+/// async fn export() -> JsonLines<impl Stream<Item = Result<Event, Infallible>>, AsResponse> {
+///     JsonLines::new(stream::iter((0..3).map(|id| Ok(Event { id }))))
+/// }
+///
+/// let app = Router::new().route("/events", get(export));
+/// # let _: Router = app;
+/// ```
+///
+/// [NDJSON]: https://github.com/ndjson/ndjson-spec
+/// [`DefaultBodyLimit`]: crate::extract::DefaultBodyLimit
+/// [`BodyReadTimeout`]: crate::extract::body_stream::BodyReadTimeout
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[must_use]
+pub struct JsonLines<S, T = AsExtractor>
+where
+    T: Mode<S>,
+{
+    // the stream of items for responses, the lines of the body for extractors
+    inner: T::Inner,
+    _marker: PhantomData<fn() -> S>,
+}
+
+mod private {
+    use super::*;
+
+    // Implemented by the marker types to pick what `JsonLines` holds.
+    pub trait Mode<S> {
+        type Inner;
+    }
+
+    pub struct Lines {
+        pub(super) body: BodyStream,
+        pub(super) buf: BytesMut,
+        // how much of `buf` is known not to contain a newline
+        pub(super) searched: usize,
+        pub(super) line: u64,
+        pub(super) policy: LineErrorPolicy,
+        pub(super) eof: bool,
+        pub(super) done: bool,
+    }
+}
+
+impl<S> Mode<S> for AsResponse {
+    type Inner = S;
+}
+
+impl<T> Mode<T> for AsExtractor {
+    type Inner = Lines;
+}
+
+/// Marker type used to indicate that [`JsonLines`] is used as an extractor.
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct AsExtractor;
+
+/// Marker type used to indicate that [`JsonLines`] is used as a response.
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct AsResponse;
+
+/// What [`JsonLines`] does with lines that can't be deserialized.
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineErrorPolicy {
+    /// Yield the error and end the stream.
+    #[default]
+    Abort,
+    /// Yield the error and continue with the next line.
+    Continue,
+    /// Ignore the line and continue with the next one.
+    Skip,
+}
+
+impl<S> JsonLines<S, AsResponse> {
+    /// Create a new `JsonLines` from a stream of items.
+    pub fn new(stream: S) -> Self {
+        Self {
+            inner: stream,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> JsonLines<T, AsExtractor> {
+    /// Set what happens with lines that can't be deserialized.
+    ///
+    /// Defaults to [`LineErrorPolicy::Abort`].
+    pub fn on_error(mut self, policy: LineErrorPolicy) -> Self {
+        self.inner.policy = policy;
+        self
+    }
+}
+
+#[async_trait]
+impl<S, T> FromRequest<S> for JsonLines<T, AsExtractor>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body = BodyStream::from_request(req, state).await?;
+        Ok(Self {
+            inner: Lines {
+                body,
+                buf: BytesMut::new(),
+                searched: 0,
+                line: 0,
+                policy: LineErrorPolicy::default(),
+                eof: false,
+                done: false,
+            },
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T> Stream for JsonLines<T, AsExtractor>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<T, JsonLinesError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().inner.poll_next_item(cx)
+    }
+}
+
+impl<S, T, E> IntoResponse for JsonLines<S, AsResponse>
+where
+    S: TryStream<Ok = T, Error = E> + Send + 'static,
+    T: Serialize,
+    E: Into<BoxError>,
+{
+    fn into_response(self) -> Response {
+        let body = Body::from_stream(self.inner.into_stream().map(|result| {
+            let value = result.map_err(Into::into)?;
+            let mut buf = crate::json::encode(&value)?;
+            buf.put_u8(b'\n');
+            Ok::<_, BoxError>(buf.freeze())
+        }));
+
+        (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/x-ndjson"),
+            )],
+            body,
+        )
+            .into_response()
+    }
+}
+
+impl<S, T> fmt::Debug for JsonLines<S, T>
+where
+    T: Mode<S>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLines").finish_non_exhaustive()
+    }
+}
+
+impl Lines {
+    fn poll_next_item<T>(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, JsonLinesError>>>
+    where
+        T: DeserializeOwned,
+    {
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            let line = if let Some(pos) = self.buf[self.searched..]
+                .iter()
+                .position(|byte| *byte == b'\n')
+            {
+                let line = self.buf.split_to(self.searched + pos + 1).freeze();
+                self.searched = 0;
+                line.slice(..line.len() - 1)
+            } else if self.eof {
+                // the last line doesn't have to end with a newline
+                self.done = true;
+                self.buf.split().freeze()
+            } else {
+                self.searched = self.buf.len();
+                match futures_util::ready!(Pin::new(&mut self.body).poll_next(cx)) {
+                    Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                    Some(Err(err)) => {
+                        self.done = true;
+                        return Poll::Ready(Some(Err(JsonLinesError::Body(err))));
+                    }
+                    None => self.eof = true,
+                }
+                continue;
+            };
+
+            self.line += 1;
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            match Json::<T>::from_bytes(&line) {
+                Ok(Json(value)) => return Poll::Ready(Some(Ok(value))),
+                Err(rejection) => {
+                    let err = JsonLinesError::Line {
+                        line: self.line,
+                        rejection,
+                    };
+                    match self.policy {
+                        LineErrorPolicy::Abort => {
+                            self.done = true;
+                            return Poll::Ready(Some(Err(err)));
+                        }
+                        LineErrorPolicy::Continue => return Poll::Ready(Some(Err(err))),
+                        LineErrorPolicy::Skip => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Errors yielded by [`JsonLines`] when used as an extractor.
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum JsonLinesError {
+    /// Reading the request body failed.
+    Body(BodyStreamError),
+    /// A line couldn't be deserialized.
+    Line {
+        /// The line number, starting at 1.
+        line: u64,
+        /// Why the line couldn't be deserialized, the same rejection [`Json`] would use.
+        rejection: JsonRejection,
+    },
+}
+
+impl fmt::Display for JsonLinesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Body(err) => write!(f, "{}", err),
+            Self::Line { line, rejection } => write!(f, "line {}: {}", line, rejection),
+        }
+    }
+}
+
+impl std::error::Error for JsonLinesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Body(err) => Some(err),
+            Self::Line { rejection, .. } => Some(rejection),
+        }
+    }
+}
+
+impl IntoResponse for JsonLinesError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Body(err) => err.into_response(),
+            Self::Line { line, rejection } => {
+                let status = rejection.status();
+                let body_text = format!("line {}: {}", line, rejection.body_text());
+                saas_core::__log_rejection!(
+                    rejection_type = JsonLinesError,
+                    body_text = body_text,
+                    status = status,
+                );
+                let mut res = (status, body_text.clone()).into_response();
+                res.extensions_mut().insert(
+                    AnyRejection::__new(
+                        status,
+                        body_text.clone(),
+                        Self::Line { line, rejection },
+                    )
+                    .__with_message(
+                        "InvalidJsonLine",
+                        "Failed to deserialize a JSON line",
+                        Some(body_text),
+                    ),
+                );
+                res
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use futures_util::stream;
+
+    #[derive(Debug, serde::Deserialize, Serialize, PartialEq)]
+    struct Item {
+        id: u64,
+    }
+
+    fn request(body: &'static str) -> Request {
+        // split the body to make sure lines spanning chunks are handled
+        let chunks = body
+            .as_bytes()
+            .chunks(3)
+            .map(|chunk| Ok::<_, Infallible>(Bytes::from_static(chunk)));
+        Request::new(Body::from_stream(stream::iter(chunks)))
+    }
+
+    #[tokio::test]
+    async fn extractor() {
+        let body = "{\"id\":1}\n\n{\"id\":\"two\"}\r\n{\"id\":3}";
+
+        let lines = JsonLines::<Item>::from_request(request(body), &())
+            .await
+            .unwrap();
+        let items = lines.collect::<Vec<_>>().await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_ref().unwrap(), &Item { id: 1 });
+        assert!(matches!(items[1], Err(JsonLinesError::Line { line: 3, .. })));
+
+        let lines = JsonLines::<Item>::from_request(request(body), &())
+            .await
+            .unwrap()
+            .on_error(LineErrorPolicy::Skip);
+        let items = lines.map(Result::unwrap).collect::<Vec<_>>().await;
+        assert_eq!(items, [Item { id: 1 }, Item { id: 3 }]);
+    }
+
+    #[tokio::test]
+    async fn response() {
+        let stream = stream::iter([Ok::<_, Infallible>(Item { id: 1 }), Ok(Item { id: 2 })]);
+        let res = JsonLines::new(stream).into_response();
+        assert_eq!(res.headers()["content-type"], "application/x-ndjson");
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"{\"id\":1}\n{\"id\":2}\n");
+    }
+}
@@ -45,6 +45,9 @@
 pub(crate) mod macros;
 
 mod boxed;
+#[cfg(feature = "cbor")]
+mod cbor;
+mod either;
 mod extension;
 
 #[cfg(feature = "form")]
@@ -54,15 +57,44 @@ mod hyper1_tokio_io;
 
 #[cfg(feature = "json")]
 mod json;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+
+#[cfg(feature = "headers")]
+mod typed_header;
 
 mod service_ext;
 mod util;
+#[cfg(feature = "xml")]
+mod xml;
+#[cfg(any(feature = "compression", feature = "decompression"))]
+mod content_coding;
 
+#[cfg(feature = "auth")]
+pub mod auth;
 pub mod body;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "conditional")]
+pub mod conditional;
+#[cfg(feature = "decompression")]
+pub mod decompression;
 pub mod error_handling;
 pub mod extract;
 pub mod handler;
+#[cfg(feature = "i18n")]
+pub mod i18n;
+#[cfg(feature = "json")]
+pub mod json_lines;
 pub mod middleware;
+#[cfg(feature = "negotiate")]
+pub mod negotiate;
+#[cfg(feature = "problem")]
+pub mod problem;
+#[cfg(feature = "range")]
+pub mod range;
 pub mod response;
 pub mod routing;
 #[cfg(feature="tokio")]
@@ -74,14 +106,39 @@ mod test_helpers;
 #[doc(no_inline)]
 pub use async_trait::async_trait;
 
+#[doc(no_inline)]
+#[cfg(feature = "headers")]
+pub use headers;
 #[doc(no_inline)]
 pub use http;
 #[doc(inline)]
 #[cfg(feature = "json")]
-pub use crate::json::Json;
+pub use crate::json::{Json, JsonConfig};
+#[doc(inline)]
+#[cfg(feature = "json")]
+pub use crate::json_lines::JsonLines;
+#[doc(inline)]
+#[cfg(feature = "msgpack")]
+pub use crate::msgpack::MsgPack;
+#[doc(inline)]
+#[cfg(feature = "cbor")]
+pub use crate::cbor::Cbor;
+#[doc(inline)]
+#[cfg(feature = "protobuf")]
+pub use crate::protobuf::Protobuf;
+#[doc(inline)]
+#[cfg(feature = "xml")]
+pub use crate::xml::Xml;
 #[doc(inline)]
 pub use self::routing::Router;
 
+#[doc(inline)]
+pub use self::either::{Either, Either3, Either4};
+
+#[doc(inline)]
+#[cfg(feature = "headers")]
+pub use self::typed_header::TypedHeader;
+
 #[doc(inline)]
 pub use crate::extension::Extension;
 
@@ -0,0 +1,178 @@
+use crate::extract::rejection::AnyRejection;
+use crate::response::{IntoResponse, Response};
+use futures_util::ready;
+use http::Request;
+use pin_project_lite::pin_project;
+use std::{
+    any::type_name,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Create a middleware that replaces the response of every rejection with the response
+/// returned by `f`.
+///
+/// `f` receives the rejection as an [`AnyRejection`], which can be downcast to the rejection
+/// type of the extractor, such as `JsonRejection` or `PathRejection`. Responses that didn't come
+/// from a rejection of a built-in extractor are passed through unchanged.
+///
+/// Usually applied with [`Router::map_rejection`](crate::Router::map_rejection).
+pub fn map_rejection<F, R>(f: F) -> MapRejectionLayer<F>
+where
+    F: Fn(AnyRejection) -> R,
+    R: IntoResponse,
+{
+    MapRejectionLayer { f }
+}
+
+/// A layer from a rejection hook.
+///
+/// Created with [`map_rejection`]. See that function for more details.
+#[derive(Clone)]
+#[must_use]
+pub struct MapRejectionLayer<F> {
+    f: F,
+}
+
+impl<F, I> Layer<I> for MapRejectionLayer<F>
+where
+    F: Clone,
+{
+    type Service = MapRejection<F, I>;
+
+    fn layer(&self, inner: I) -> Self::Service {
+        MapRejection {
+            f: self.f.clone(),
+            inner,
+        }
+    }
+}
+
+impl<F> fmt::Debug for MapRejectionLayer<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapRejectionLayer")
+            .field("f", &format_args!("{}", type_name::<F>()))
+            .finish()
+    }
+}
+
+/// A middleware created from a rejection hook.
+///
+/// Created with [`map_rejection`]. See that function for more details.
+#[derive(Clone)]
+pub struct MapRejection<F, I> {
+    f: F,
+    inner: I,
+}
+
+impl<F, R, I, B> Service<Request<B>> for MapRejection<F, I>
+where
+    F: Fn(AnyRejection) -> R + Clone,
+    R: IntoResponse,
+    I: Service<Request<B>, Response = Response>,
+{
+    type Response = Response;
+    type Error = I::Error;
+    type Future = ResponseFuture<I::Future, F>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(req),
+            f: self.f.clone(),
+        }
+    }
+}
+
+impl<F, I> fmt::Debug for MapRejection<F, I>
+where
+    I: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapRejection")
+            .field("f", &format_args!("{}", type_name::<F>()))
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+pin_project! {
+    /// Response future for [`MapRejection`].
+    pub struct ResponseFuture<Fut, F> {
+        #[pin]
+        inner: Fut,
+        f: F,
+    }
+}
+
+impl<Fut, F> fmt::Debug for ResponseFuture<Fut, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish_non_exhaustive()
+    }
+}
+
+impl<Fut, F, R, E> Future for ResponseFuture<Fut, F>
+where
+    Fut: Future<Output = Result<Response, E>>,
+    F: Fn(AnyRejection) -> R,
+    R: IntoResponse,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut res = ready!(this.inner.poll(cx))?;
+
+        if let Some(rejection) = res.extensions_mut().remove::<AnyRejection>() {
+            return Poll::Ready(Ok((this.f)(rejection).into_response()));
+        }
+
+        Poll::Ready(Ok(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::rejection::{
+        FailedToDeserializeQueryString, JsonRejection, MissingJsonContentType, QueryRejection,
+    };
+    use http::StatusCode;
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn composite_rejections_are_typed() {
+        let res = JsonRejection::from(MissingJsonContentType).into_response();
+        let rejection = res.extensions().get::<AnyRejection>().unwrap();
+
+        assert_eq!(rejection.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert!(matches!(
+            rejection.downcast_ref::<JsonRejection>(),
+            Some(JsonRejection::MissingJsonContentType(_))
+        ));
+        // the concrete rejection is only known by name
+        assert!(!rejection.is::<MissingJsonContentType>());
+        assert_eq!(rejection.name(), "MissingJsonContentType");
+    }
+
+    #[test]
+    fn exposes_source() {
+        let rejection = FailedToDeserializeQueryString::from_err("missing field `page`");
+        let res = QueryRejection::from(rejection).into_response();
+        let rejection = res.extensions().get::<AnyRejection>().unwrap();
+
+        assert_eq!(
+            rejection.body_text(),
+            "Failed to deserialize query string: missing field `page`"
+        );
+        assert_eq!(rejection.source().unwrap().to_string(), "missing field `page`");
+    }
+}
@@ -1,5 +1,6 @@
 mod from_extractor;
 mod from_fn;
+mod map_rejection;
 mod map_request;
 mod map_response;
 
@@ -7,6 +8,7 @@ pub use self::from_extractor::{
     from_extractor, from_extractor_with_state, FromExtractor, FromExtractorLayer,
 };
 pub use self::from_fn::{from_fn, from_fn_with_state, FromFn, FromFnLayer, Next};
+pub use self::map_rejection::{map_rejection, MapRejection, MapRejectionLayer};
 pub use self::map_request::{
     map_request, map_request_with_state, IntoMapRequestResult, MapRequest, MapRequestLayer
 };
@@ -19,6 +21,7 @@ pub use crate::extension::AddExtension;
 pub mod future {
     pub use super::from_extractor::ResponseFuture as FromExtractorResponseFuture;
     pub use super::from_fn::ResponseFuture as FromFnResponseFuture;
+    pub use super::map_rejection::ResponseFuture as MapRejectionResponseFuture;
     pub use super::map_request::ResponseFuture as MapRequestResponseFuture;
     pub use super::map_response::ResponseFuture as MapResponseResponseFuture;
 }
\ No newline at end of file
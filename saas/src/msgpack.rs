@@ -0,0 +1,220 @@
+use crate::extract::Request;
+use crate::extract::{rejection::*, FromRequest};
+use async_trait::async_trait;
+use bytes::{BufMut, Bytes, BytesMut};
+use http::{
+    header::{self, HeaderMap, HeaderValue},
+    StatusCode,
+};
+use saas_core::response::{IntoResponse, Response};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Serialize,
+};
+
+/// MessagePack extractor and response.
+///
+/// Works the same way as [`Json`](crate::Json) but for [MessagePack] bodies.
+///
+/// # As extractor
+///
+/// The request is rejected if it doesn't have a `Content-Type` of `application/msgpack`,
+/// `application/x-msgpack`, `application/vnd.msgpack` or `application/*+msgpack`, if the body
+/// isn't valid MessagePack, or if it can't be deserialized into the target type.
+///
+/// ```rust,no_run
+/// use saas::{routing::post, MsgPack, Router};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Reading {
+///     sensor: String,
+///     value: f64,
+/// }
+///
+/// async fn record(MsgPack(reading): MsgPack<Reading>) {
+///     // ...
+/// }
+///
+/// let app = Router::new().route("/readings", post(record));
+/// # let _: Router = app;
+/// ```
+///
+/// # As response
+///
+/// Structs are encoded as maps with the field names as keys, with
+/// `Content-Type: application/msgpack`.
+///
+/// [MessagePack]: https://msgpack.org
+#[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+#[derive(Debug, Clone, Copy, Default)]
+#[must_use]
+pub struct MsgPack<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for MsgPack<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = MsgPackRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if msgpack_content_type(req.headers()) {
+            let bytes = Bytes::from_request(req, state).await?;
+            Self::from_bytes(&bytes)
+        } else {
+            Err(MissingMsgPackContentType.into())
+        }
+    }
+}
+
+impl<T> MsgPack<T>
+where
+    T: DeserializeOwned,
+{
+    /// Construct a `MsgPack<T>` from a byte slice.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MsgPackRejection> {
+        let mut reader = bytes;
+
+        let result = {
+            let deserializer = &mut rmp_serde::Deserializer::new(&mut reader);
+            serde_path_to_error::deserialize(deserializer)
+        };
+
+        match result {
+            // the body must contain a single value
+            Ok(_) if !reader.is_empty() => {
+                Err(
+                    MsgPackSyntaxError::from_err("trailing bytes after the MessagePack value")
+                        .into(),
+                )
+            }
+            Ok(value) => Ok(MsgPack(value)),
+            Err(err) => {
+                // rmp-serde reports custom errors, such as missing fields, as syntax errors so
+                // check whether the body itself is valid instead
+                if rmp_serde::from_slice::<IgnoredAny>(bytes).is_ok() {
+                    Err(MsgPackDataError::from_err(err).into())
+                } else {
+                    Err(MsgPackSyntaxError::from_err(err).into())
+                }
+            }
+        }
+    }
+}
+
+fn msgpack_content_type(headers: &HeaderMap) -> bool {
+    let mime = match headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.parse::<mime::Mime>().ok())
+    {
+        Some(mime) => mime,
+        None => return false,
+    };
+
+    is_msgpack(&mime)
+}
+
+pub(crate) fn is_msgpack(mime: &mime::Mime) -> bool {
+    mime.type_() == "application"
+        && (matches!(
+            mime.subtype().as_str(),
+            "msgpack" | "x-msgpack" | "vnd.msgpack"
+        ) || mime.suffix().is_some_and(|name| name == "msgpack"))
+}
+
+saas_core::__impl_deref!(MsgPack);
+
+impl<T> From<T> for MsgPack<T> {
+    fn from(inner: T) -> Self {
+        Self(inner)
+    }
+}
+
+impl<T> IntoResponse for MsgPack<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        let mut buf = BytesMut::with_capacity(128).writer();
+
+        match rmp_serde::encode::write_named(&mut buf, &self.0) {
+            Ok(()) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/msgpack"),
+                )],
+                buf.into_inner().freeze(),
+            )
+                .into_response(),
+
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(mime::TEXT_PLAIN_UTF_8.as_ref()),
+                )],
+                err.to_string(),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Reading {
+        sensor: String,
+        value: u32,
+    }
+
+    #[test]
+    fn content_type() {
+        let mut headers = HeaderMap::new();
+        for content_type in ["application/msgpack", "application/vnd.foo+msgpack"] {
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            assert!(msgpack_content_type(&headers));
+        }
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        assert!(!msgpack_content_type(&headers));
+    }
+
+    #[test]
+    fn data_and_syntax_errors() {
+        let reading = Reading {
+            sensor: "a".to_owned(),
+            value: 1,
+        };
+        let bytes = rmp_serde::to_vec_named(&reading).unwrap();
+        assert_eq!(MsgPack::<Reading>::from_bytes(&bytes).unwrap().0, reading);
+
+        let bytes = rmp_serde::to_vec_named(&"not a reading").unwrap();
+        assert!(matches!(
+            MsgPack::<Reading>::from_bytes(&bytes),
+            Err(MsgPackRejection::MsgPackDataError(_))
+        ));
+
+        assert!(matches!(
+            MsgPack::<Reading>::from_bytes(&[0xc1]),
+            Err(MsgPackRejection::MsgPackSyntaxError(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        let mut bytes = rmp_serde::to_vec(&1u32).unwrap();
+        assert_eq!(MsgPack::<u32>::from_bytes(&bytes).unwrap().0, 1);
+
+        bytes.extend(rmp_serde::to_vec(&2u32).unwrap());
+        assert!(matches!(
+            MsgPack::<u32>::from_bytes(&bytes),
+            Err(MsgPackRejection::MsgPackSyntaxError(_))
+        ));
+    }
+}
@@ -0,0 +1,734 @@
+//! Content negotiation for request and response bodies.
+//!
+//! See [`Negotiated`] for more details.
+
+use crate::{
+    extract::rejection::{
+        FailedToDecodeBody, FailedToDeserializeBody, NegotiatedRejection, NotAcceptable,
+        UnsupportedContentType,
+    },
+    Json,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{
+    header::{self, HeaderValue},
+    request::Parts,
+    StatusCode,
+};
+use mime::Mime;
+use saas_core::{
+    extract::{FromRequest, FromRequestParts, Request},
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{convert::Infallible, fmt, marker::PhantomData};
+
+/// Extractor and response that picks the body format from the request headers.
+///
+/// # As extractor
+///
+/// The body is decoded with the first codec in `C` that matches the `Content-Type` of the
+/// request. If none matches the request is rejected with `415 Unsupported Media Type`.
+///
+/// # As response
+///
+/// The body is encoded with the codec the client prefers according to the q-values of the
+/// `Accept` header, and `Vary: Accept` is added. Ties are broken by the order of the codecs in
+/// `C`. If none of the codecs is acceptable `406 Not Acceptable` is returned, and if encoding
+/// fails `500 Internal Server Error`.
+///
+/// The `Accept` header is part of the request, so responses are only negotiated if they are
+/// created from it: with [`Negotiated::map`] on an extracted `Negotiated`, or with
+/// [`Negotiator::respond`]. A `Negotiated` created with [`Negotiated::new`] is always encoded
+/// with the first codec.
+///
+/// The default codecs are [`JsonCodec`], [`FormCodec`] and [`MsgPackCodec`]. `CborCodec` is
+/// available with the `cbor` feature, other formats can be plugged in by implementing
+/// [`Codec`]. For example `Negotiated<T, (JsonCodec, CborCodec)>`.
+///
+/// ```rust,no_run
+/// use saas::{
+///     negotiate::{Negotiated, Negotiator},
+///     routing::get,
+///     Router,
+/// };
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize)]
+/// struct CreateUser {
+///     name: String,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     id: u64,
+///     name: String,
+/// }
+///
+/// async fn create_user(payload: Negotiated<CreateUser>) -> Negotiated<User> {
+///     payload.map(|payload| User { id: 1, name: payload.name })
+/// }
+///
+/// async fn get_user(negotiator: Negotiator) -> Negotiated<User> {
+///     negotiator.respond(User { id: 1, name: "Alice".to_owned() })
+/// }
+///
+/// let app = Router::new().route("/users", get(get_user).post(create_user));
+/// # let _: Router = app;
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+#[must_use]
+pub struct Negotiated<T, C = DefaultCodecs>(pub T, Preference, PhantomData<fn() -> C>);
+
+// How the format of a response is picked.
+#[derive(Debug, Clone)]
+enum Preference {
+    // created with `Negotiated::new`, encoded with the first codec
+    FirstCodec,
+    // the `Accept` header of the request the response is for
+    Accept(Option<HeaderValue>),
+}
+
+impl<T, C> Negotiated<T, C> {
+    /// Create a new `Negotiated` that is encoded with the first codec.
+    pub fn new(value: T) -> Self {
+        Self(value, Preference::FirstCodec, PhantomData)
+    }
+
+    /// Consumes the `Negotiated`, returning the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    /// Maps the wrapped value, keeping the `Accept` header of the request it was extracted from
+    /// so the result is encoded in the format the client prefers.
+    pub fn map<U, F>(self, f: F) -> Negotiated<U, C>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Negotiated(f(self.0), self.1, PhantomData)
+    }
+}
+
+impl<T, C> From<T> for Negotiated<T, C> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T, C> fmt::Debug for Negotiated<T, C>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Negotiated").field(&self.0).finish()
+    }
+}
+
+impl<T, C> Clone for Negotiated<T, C>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), self.1.clone(), PhantomData)
+    }
+}
+
+impl<T, C> std::ops::Deref for Negotiated<T, C> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, C> std::ops::DerefMut for Negotiated<T, C> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[async_trait]
+impl<T, C, S> FromRequest<S> for Negotiated<T, C>
+where
+    T: DeserializeOwned,
+    C: Codecs,
+    S: Send + Sync,
+{
+    type Rejection = NegotiatedRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok())
+            .ok_or(UnsupportedContentType)?;
+
+        if C::decoder_for(&content_type).is_none() {
+            return Err(UnsupportedContentType.into());
+        }
+
+        let accept = Preference::Accept(req.headers().get(header::ACCEPT).cloned());
+        let bytes = Bytes::from_request(req, state).await?;
+        match C::decode(&content_type, &bytes) {
+            Some(Ok(value)) => Ok(Self(value, accept, PhantomData)),
+            Some(Err(CodecError::Syntax(err))) => Err(FailedToDecodeBody::from_err(err).into()),
+            Some(Err(CodecError::Data(err))) => Err(FailedToDeserializeBody::from_err(err).into()),
+            None => Err(UnsupportedContentType.into()),
+        }
+    }
+}
+
+impl<T, C> IntoResponse for Negotiated<T, C>
+where
+    T: Serialize,
+    C: Codecs,
+{
+    fn into_response(self) -> Response {
+        let accept = match self.1 {
+            Preference::FirstCodec => return encode::<T, C>(0, &self.0),
+            Preference::Accept(accept) => accept,
+        };
+
+        let mut res = match negotiate::<C>(accept.as_ref()) {
+            Some(idx) => encode::<T, C>(idx, &self.0),
+            None => NotAcceptable.into_response(),
+        };
+        res.headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept"));
+        res
+    }
+}
+
+fn encode<T, C>(idx: usize, value: &T) -> Response
+where
+    T: Serialize,
+    C: Codecs,
+{
+    match C::encode(idx, value) {
+        Some(Ok(bytes)) => (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(C::CONTENT_TYPES[idx]),
+            )],
+            bytes,
+        )
+            .into_response(),
+        Some(Err(err)) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+        None => (StatusCode::INTERNAL_SERVER_ERROR, "no codec at index").into_response(),
+    }
+}
+
+/// Extractor for the `Accept` header of the request, used to create [`Negotiated`] responses
+/// in handlers that don't extract a `Negotiated` body.
+///
+/// See [`Negotiated`] for more details.
+#[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+pub struct Negotiator<C = DefaultCodecs> {
+    accept: Option<HeaderValue>,
+    _codecs: PhantomData<fn() -> C>,
+}
+
+impl<C> Negotiator<C> {
+    /// Create a response that is encoded in the format the client prefers.
+    pub fn respond<T>(&self, value: T) -> Negotiated<T, C> {
+        Negotiated(value, Preference::Accept(self.accept.clone()), PhantomData)
+    }
+}
+
+impl<C> fmt::Debug for Negotiator<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Negotiator")
+            .field("accept", &self.accept)
+            .finish()
+    }
+}
+
+impl<C> Clone for Negotiator<C> {
+    fn clone(&self) -> Self {
+        Self {
+            accept: self.accept.clone(),
+            _codecs: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<C, S> FromRequestParts<S> for Negotiator<C>
+where
+    C: Codecs,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            accept: parts.headers.get(header::ACCEPT).cloned(),
+            _codecs: PhantomData,
+        })
+    }
+}
+
+/// Errors returned by [`Codec::decode`].
+#[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+#[derive(Debug)]
+pub enum CodecError {
+    /// The body isn't syntactically valid for the format.
+    Syntax(BoxError),
+    /// The body is syntactically valid but couldn't be deserialized into the target type.
+    Data(BoxError),
+}
+
+/// A body format that can be used with [`Negotiated`].
+#[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+pub trait Codec: Send + Sync + 'static {
+    /// The `Content-Type` of responses encoded with this codec.
+    const CONTENT_TYPE: &'static str;
+
+    /// Whether a request with this `Content-Type` can be decoded.
+    fn matches(content_type: &Mime) -> bool;
+
+    /// Decode a request body.
+    fn decode<T>(bytes: &[u8]) -> Result<T, CodecError>
+    where
+        T: DeserializeOwned;
+
+    /// Encode a response body.
+    fn encode<T>(value: &T) -> Result<Bytes, BoxError>
+    where
+        T: Serialize;
+}
+
+/// An ordered list of [`Codec`]s.
+///
+/// Implemented for tuples of up to 8 codecs. The order is the server's preference.
+#[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+pub trait Codecs: Send + Sync + 'static {
+    /// The `Content-Type`s of the codecs, in order.
+    const CONTENT_TYPES: &'static [&'static str];
+
+    /// Index of the first codec that can decode `content_type`.
+    fn decoder_for(content_type: &Mime) -> Option<usize>;
+
+    /// Decode with the first codec that can decode `content_type`.
+    fn decode<T>(content_type: &Mime, bytes: &[u8]) -> Option<Result<T, CodecError>>
+    where
+        T: DeserializeOwned;
+
+    /// Encode with the codec at `idx`.
+    fn encode<T>(idx: usize, value: &T) -> Option<Result<Bytes, BoxError>>
+    where
+        T: Serialize;
+}
+
+macro_rules! impl_codecs {
+    ( $($ty:ident),* $(,)? ) => {
+        #[allow(non_snake_case, unused_assignments)]
+        impl<$($ty,)*> Codecs for ($($ty,)*)
+        where
+            $( $ty: Codec, )*
+        {
+            const CONTENT_TYPES: &'static [&'static str] = &[$($ty::CONTENT_TYPE,)*];
+
+            fn decoder_for(content_type: &Mime) -> Option<usize> {
+                let mut idx = 0;
+                $(
+                    if $ty::matches(content_type) {
+                        return Some(idx);
+                    }
+                    idx += 1;
+                )*
+                None
+            }
+
+            fn decode<T>(content_type: &Mime, bytes: &[u8]) -> Option<Result<T, CodecError>>
+            where
+                T: DeserializeOwned,
+            {
+                $(
+                    if $ty::matches(content_type) {
+                        return Some($ty::decode(bytes));
+                    }
+                )*
+                None
+            }
+
+            fn encode<T>(idx: usize, value: &T) -> Option<Result<Bytes, BoxError>>
+            where
+                T: Serialize,
+            {
+                let mut idx = idx;
+                $(
+                    if idx == 0 {
+                        return Some($ty::encode(value));
+                    }
+                    idx -= 1;
+                )*
+                None
+            }
+        }
+    };
+}
+
+impl_codecs!(C1);
+impl_codecs!(C1, C2);
+impl_codecs!(C1, C2, C3);
+impl_codecs!(C1, C2, C3, C4);
+impl_codecs!(C1, C2, C3, C4, C5);
+impl_codecs!(C1, C2, C3, C4, C5, C6);
+impl_codecs!(C1, C2, C3, C4, C5, C6, C7);
+impl_codecs!(C1, C2, C3, C4, C5, C6, C7, C8);
+
+/// The codecs used by [`Negotiated`] if none are specified.
+#[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+pub type DefaultCodecs = (JsonCodec, FormCodec, MsgPackCodec);
+
+/// [`Codec`] for `application/json`, using the same logic as [`Json`].
+#[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    const CONTENT_TYPE: &'static str = "application/json";
+
+    fn matches(content_type: &Mime) -> bool {
+        content_type.type_() == "application"
+            && (content_type.subtype() == "json"
+                || content_type.suffix().is_some_and(|name| name == "json"))
+    }
+
+    fn decode<T>(bytes: &[u8]) -> Result<T, CodecError>
+    where
+        T: DeserializeOwned,
+    {
+        use crate::extract::rejection::JsonRejection;
+
+        match Json::<T>::from_bytes(bytes) {
+            Ok(Json(value)) => Ok(value),
+            Err(JsonRejection::JsonDataError(err)) => Err(CodecError::Data(err.into())),
+            Err(err) => Err(CodecError::Syntax(err.into())),
+        }
+    }
+
+    fn encode<T>(value: &T) -> Result<Bytes, BoxError>
+    where
+        T: Serialize,
+    {
+        Ok(crate::json::encode(value)?.freeze())
+    }
+}
+
+/// [`Codec`] for `application/x-www-form-urlencoded`, using the same logic as
+/// [`Form`](crate::extract::Form).
+#[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct FormCodec;
+
+impl Codec for FormCodec {
+    const CONTENT_TYPE: &'static str = "application/x-www-form-urlencoded";
+
+    fn matches(content_type: &Mime) -> bool {
+        content_type.essence_str() == mime::APPLICATION_WWW_FORM_URLENCODED.essence_str()
+    }
+
+    fn decode<T>(bytes: &[u8]) -> Result<T, CodecError>
+    where
+        T: DeserializeOwned,
+    {
+        crate::form::decode(bytes).map_err(|err| CodecError::Data(err.into()))
+    }
+
+    fn encode<T>(value: &T) -> Result<Bytes, BoxError>
+    where
+        T: Serialize,
+    {
+        Ok(crate::form::encode(value)?.into())
+    }
+}
+
+/// [`Codec`] for `application/msgpack`, using the same logic as [`MsgPack`](crate::MsgPack).
+#[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct MsgPackCodec;
+
+impl Codec for MsgPackCodec {
+    const CONTENT_TYPE: &'static str = "application/msgpack";
+
+    fn matches(content_type: &Mime) -> bool {
+        crate::msgpack::is_msgpack(content_type)
+    }
+
+    fn decode<T>(bytes: &[u8]) -> Result<T, CodecError>
+    where
+        T: DeserializeOwned,
+    {
+        use crate::extract::rejection::MsgPackRejection;
+
+        match crate::MsgPack::<T>::from_bytes(bytes) {
+            Ok(crate::MsgPack(value)) => Ok(value),
+            Err(MsgPackRejection::MsgPackDataError(err)) => Err(CodecError::Data(err.into())),
+            Err(err) => Err(CodecError::Syntax(err.into())),
+        }
+    }
+
+    fn encode<T>(value: &T) -> Result<Bytes, BoxError>
+    where
+        T: Serialize,
+    {
+        Ok(rmp_serde::to_vec_named(value)?.into())
+    }
+}
+
+/// [`Codec`] for `application/cbor`, using the same logic as [`Cbor`](crate::Cbor).
+#[cfg(feature = "cbor")]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "negotiate", feature = "cbor"))))]
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct CborCodec;
+
+#[cfg(feature = "cbor")]
+impl Codec for CborCodec {
+    const CONTENT_TYPE: &'static str = "application/cbor";
+
+    fn matches(content_type: &Mime) -> bool {
+        crate::cbor::is_cbor(content_type)
+    }
+
+    fn decode<T>(bytes: &[u8]) -> Result<T, CodecError>
+    where
+        T: DeserializeOwned,
+    {
+        use crate::extract::rejection::CborRejection;
+
+        match crate::Cbor::<T>::from_bytes(bytes) {
+            Ok(crate::Cbor(value)) => Ok(value),
+            Err(CborRejection::CborDataError(err)) => Err(CodecError::Data(err.into())),
+            Err(err) => Err(CodecError::Syntax(err.into())),
+        }
+    }
+
+    fn encode<T>(value: &T) -> Result<Bytes, BoxError>
+    where
+        T: Serialize,
+    {
+        let mut buf = Vec::with_capacity(128);
+        ciborium::ser::into_writer(value, &mut buf)?;
+        Ok(buf.into())
+    }
+}
+
+/// Returns the index of the codec to use for a response, or `None` if no codec is acceptable.
+fn negotiate<C>(accept: Option<&HeaderValue>) -> Option<usize>
+where
+    C: Codecs,
+{
+    let accept = match accept.and_then(|value| value.to_str().ok()) {
+        Some(accept) if !accept.trim().is_empty() => accept,
+        _ => return Some(0),
+    };
+
+    let ranges = accept
+        .split(',')
+        .filter_map(|range| range.trim().parse::<Mime>().ok())
+        .map(|range| {
+            let q = range
+                .get_param("q")
+                .and_then(|q| q.as_str().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (range, q)
+        })
+        .collect::<Vec<_>>();
+
+    let mut best: Option<(usize, f32)> = None;
+    for (idx, content_type) in C::CONTENT_TYPES.iter().enumerate() {
+        let content_type = match content_type.parse::<Mime>() {
+            Ok(content_type) => content_type,
+            Err(_) => continue,
+        };
+
+        // the most specific matching range decides the quality
+        let q = ranges
+            .iter()
+            .filter_map(|(range, q)| specificity(range, &content_type).map(|s| (s, *q)))
+            .max_by_key(|(s, _)| *s)
+            .map_or(0.0, |(_, q)| q);
+
+        if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((idx, q));
+        }
+    }
+
+    best.map(|(idx, _)| idx)
+}
+
+fn specificity(range: &Mime, content_type: &Mime) -> Option<u8> {
+    if range.type_() == mime::STAR && range.subtype() == mime::STAR {
+        Some(0)
+    } else if range.type_() == content_type.type_() && range.subtype() == mime::STAR {
+        Some(1)
+    } else if range.essence_str() == content_type.essence_str() {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pick(accept: &'static str) -> Option<&'static str> {
+        negotiate::<DefaultCodecs>(Some(&HeaderValue::from_static(accept)))
+            .map(|idx| DefaultCodecs::CONTENT_TYPES[idx])
+    }
+
+    #[test]
+    fn negotiates_by_q_value() {
+        assert_eq!(pick("*/*"), Some("application/json"));
+        assert_eq!(
+            pick("application/json;q=0.5, application/x-www-form-urlencoded"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(
+            pick("application/*;q=0.9, application/json;q=0"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(pick("text/html"), None);
+    }
+
+    mod response {
+        use super::*;
+        use crate::{body::Body, routing::get, Router};
+        use serde_json::{json, Value};
+        use std::collections::BTreeMap;
+        use tower::ServiceExt;
+
+        struct FailingCodec;
+
+        impl Codec for FailingCodec {
+            const CONTENT_TYPE: &'static str = "application/x-failing";
+
+            fn matches(_: &Mime) -> bool {
+                false
+            }
+
+            fn decode<T>(_: &[u8]) -> Result<T, CodecError>
+            where
+                T: DeserializeOwned,
+            {
+                Err(CodecError::Syntax("unsupported".into()))
+            }
+
+            fn encode<T>(_: &T) -> Result<Bytes, BoxError>
+            where
+                T: Serialize,
+            {
+                panic!("encoded with a codec that wasn't negotiated")
+            }
+        }
+
+        async fn send(app: Router, accept: &str) -> Response {
+            let req = http::Request::get("/")
+                .header(header::ACCEPT, accept)
+                .body(Body::empty())
+                .unwrap();
+            app.oneshot(req).await.unwrap()
+        }
+
+        async fn user(negotiator: Negotiator) -> Negotiated<Value> {
+            negotiator.respond(json!({ "id": 1 }))
+        }
+
+        #[tokio::test]
+        async fn encodes_with_the_negotiated_codec() {
+            let app = Router::new().route("/", get(user));
+
+            let res = send(app.clone(), "application/msgpack").await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.headers()[header::CONTENT_TYPE], "application/msgpack");
+            assert_eq!(res.headers()[header::VARY], "accept");
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let value: Value = rmp_serde::from_slice(&body).unwrap();
+            assert_eq!(value, json!({ "id": 1 }));
+
+            let res = send(app, "text/html").await;
+            assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+            assert_eq!(res.headers()[header::VARY], "accept");
+        }
+
+        #[tokio::test]
+        async fn keeps_the_accept_header_of_the_extracted_body() {
+            let app = Router::new().route(
+                "/",
+                get(|payload: Negotiated<Value>| async move {
+                    payload.map(|payload| json!({ "received": payload }))
+                }),
+            );
+
+            let req = http::Request::get("/")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::ACCEPT, "application/msgpack")
+                .body(Body::from(r#"{"id":1}"#))
+                .unwrap();
+            let res = app.oneshot(req).await.unwrap();
+            assert_eq!(res.headers()[header::CONTENT_TYPE], "application/msgpack");
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let value: Value = rmp_serde::from_slice(&body).unwrap();
+            assert_eq!(value, json!({ "received": { "id": 1 } }));
+        }
+
+        #[tokio::test]
+        async fn only_encodes_with_the_negotiated_codec() {
+            async fn handler(
+                negotiator: Negotiator<(FailingCodec, JsonCodec)>,
+            ) -> Negotiated<Value, (FailingCodec, JsonCodec)> {
+                negotiator.respond(json!({ "id": 1 }))
+            }
+
+            let app = Router::new().route("/", get(handler));
+
+            let res = send(app, "application/json").await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, r#"{"id":1}"#);
+        }
+
+        #[tokio::test]
+        async fn encoding_errors_are_server_errors() {
+            async fn handler(negotiator: Negotiator) -> Negotiated<BTreeMap<(u8, u8), u8>> {
+                // JSON object keys must be strings
+                negotiator.respond(BTreeMap::from([((1, 2), 3)]))
+            }
+
+            let app = Router::new().route("/", get(handler));
+
+            let res = send(app, "application/json").await;
+            assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+            assert_eq!(res.headers()[header::VARY], "accept");
+        }
+
+        #[tokio::test]
+        async fn first_codec_without_accept() {
+            async fn handler() -> Negotiated<Value> {
+                Negotiated::new(json!({ "id": 1 }))
+            }
+
+            let app = Router::new().route("/", get(handler));
+
+            let res = send(app, "application/msgpack").await;
+            assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+            assert!(res.headers().get(header::VARY).is_none());
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, r#"{"id":1}"#);
+        }
+    }
+}
@@ -0,0 +1,429 @@
+//! [RFC 7807] Problem Details responses.
+//!
+//! [`Problem`] is a response for application errors:
+//!
+//! ```rust,no_run
+//! use saas::{http::StatusCode, problem::Problem, routing::post, Router};
+//!
+//! async fn transfer() -> Result<(), Problem> {
+//!     Err(Problem::new(StatusCode::FORBIDDEN)
+//!         .with_type("https://example.com/probs/out-of-credit")
+//!         .with_title("You do not have enough credit.")
+//!         .with_detail("Your current balance is 30, but that costs 50.")
+//!         .with_extension("balance", 30))
+//! }
+//!
+//! let app = Router::new().route("/transfer", post(transfer));
+//! # let _: Router = app;
+//! ```
+//!
+//! [`ProblemDetailsLayer`] renders the built-in rejections, which are `text/plain` by default,
+//! as problem details:
+//!
+//! ```rust,no_run
+//! use saas::{problem::ProblemDetailsLayer, routing::post, Json, Router};
+//! use serde_json::Value;
+//!
+//! async fn create_user(Json(payload): Json<Value>) {}
+//!
+//! let app = Router::new()
+//!     .route("/users", post(create_user))
+//!     .layer(ProblemDetailsLayer::new());
+//! # let _: Router = app;
+//! ```
+//!
+//! A request without a JSON `Content-Type` then gets:
+//!
+//! ```json
+//! {
+//!   "type": "about:blank",
+//!   "title": "Unsupported Media Type",
+//!   "status": 415,
+//!   "detail": "Expected request with `Content-Type: application/json`"
+//! }
+//! ```
+//!
+//! [RFC 7807]: https://www.rfc-editor.org/rfc/rfc7807
+
+use crate::extract::rejection::AnyRejection;
+use futures_util::ready;
+use http::{
+    header::{self, HeaderValue},
+    Request, StatusCode,
+};
+use pin_project_lite::pin_project;
+use saas_core::{
+    body::Body,
+    response::{IntoResponse, Response},
+};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::{
+    borrow::Cow,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
+
+/// A Problem Details object, rendered as `application/problem+json`.
+///
+/// `type` defaults to `about:blank`, in which case `title` defaults to the reason phrase of
+/// the status. See the [module docs](self) for an example.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Problem {
+    status: StatusCode,
+    type_: Option<String>,
+    title: Option<String>,
+    detail: Option<String>,
+    instance: Option<String>,
+    extensions: Map<String, Value>,
+}
+
+impl Problem {
+    /// Create a new `Problem` with the given status.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            type_: None,
+            title: None,
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Set the URI that identifies the problem type.
+    pub fn with_type(mut self, type_: impl Into<String>) -> Self {
+        self.type_ = Some(type_.into());
+        self
+    }
+
+    /// Set the short summary of the problem type.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the explanation specific to this occurrence of the problem.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Set the URI that identifies this occurrence of the problem.
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extension member.
+    ///
+    /// # Panics
+    ///
+    /// If `value` can't be serialized to JSON, or if `name` is one of the standard members.
+    pub fn with_extension(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let name = name.into();
+        assert!(
+            !matches!(
+                name.as_str(),
+                "type" | "title" | "status" | "detail" | "instance"
+            ),
+            "`{}` is a standard problem details member",
+            name
+        );
+        let value = serde_json::to_value(value).expect("extension must be serializable to JSON");
+        self.extensions.insert(name, value);
+        self
+    }
+
+    /// The status.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    fn title(&self) -> Option<Cow<'_, str>> {
+        match (&self.title, &self.type_) {
+            (Some(title), _) => Some(Cow::Borrowed(title)),
+            (None, None) => self.status.canonical_reason().map(Cow::Borrowed),
+            (None, Some(_)) => None,
+        }
+    }
+}
+
+impl Serialize for Problem {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("type", self.type_.as_deref().unwrap_or("about:blank"))?;
+        if let Some(title) = self.title() {
+            map.serialize_entry("title", &title)?;
+        }
+        map.serialize_entry("status", &self.status.as_u16())?;
+        if let Some(detail) = &self.detail {
+            map.serialize_entry("detail", detail)?;
+        }
+        if let Some(instance) = &self.instance {
+            map.serialize_entry("instance", instance)?;
+        }
+        for (name, value) in &self.extensions {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.title(), &self.detail) {
+            (Some(title), Some(detail)) => write!(f, "{}: {}", title, detail),
+            (Some(title), None) => f.write_str(&title),
+            (None, Some(detail)) => f.write_str(detail),
+            (None, None) => write!(f, "{}", self.status),
+        }
+    }
+}
+
+impl std::error::Error for Problem {}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        match serde_json::to_vec(&self) {
+            Ok(body) => (
+                self.status,
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(APPLICATION_PROBLEM_JSON),
+                )],
+                body,
+            )
+                .into_response(),
+
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(mime::TEXT_PLAIN_UTF_8.as_ref()),
+                )],
+                err.to_string(),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Layer that renders rejections as problem details.
+///
+/// Applies to the rejections of the built-in extractors. The body text of the rejection
+/// becomes the `detail`. See the [module docs](self) for an example.
+#[derive(Debug, Clone, Default)]
+pub struct ProblemDetailsLayer {
+    type_base: Option<Arc<str>>,
+}
+
+impl ProblemDetailsLayer {
+    /// Create a new `ProblemDetailsLayer`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `type` of rejections to `base` followed by the name of the rejection, such as
+    /// `https://example.com/problems/MissingJsonContentType`.
+    ///
+    /// The `title` is then the fixed description of the rejection, instead of the reason
+    /// phrase of the status.
+    pub fn type_base(mut self, base: impl Into<String>) -> Self {
+        self.type_base = Some(base.into().into());
+        self
+    }
+}
+
+impl<S> Layer<S> for ProblemDetailsLayer {
+    type Service = ProblemDetails<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ProblemDetails {
+            inner,
+            type_base: self.type_base.clone(),
+        }
+    }
+}
+
+/// Middleware that renders rejections as problem details.
+///
+/// Created with [`ProblemDetailsLayer`].
+#[derive(Debug, Clone)]
+pub struct ProblemDetails<S> {
+    inner: S,
+    type_base: Option<Arc<str>>,
+}
+
+impl<B, S> Service<Request<B>> for ProblemDetails<S>
+where
+    S: Service<Request<B>, Response = Response>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(req),
+            type_base: self.type_base.clone(),
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`ProblemDetails`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        type_base: Option<Arc<str>>,
+    }
+}
+
+impl<F> fmt::Debug for ResponseFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture")
+            .field("type_base", &self.type_base)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response, E>>,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut res = ready!(this.inner.poll(cx))?;
+
+        if let Some(rejection) = res.extensions().get::<AnyRejection>() {
+            let problem = rejection_problem(res.status(), rejection, this.type_base.as_deref());
+            if let Ok(body) = serde_json::to_vec(&problem) {
+                let headers = res.headers_mut();
+                headers.remove(header::CONTENT_LENGTH);
+                headers.insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(APPLICATION_PROBLEM_JSON),
+                );
+                *res.body_mut() = Body::from(body);
+            }
+        }
+
+        Poll::Ready(Ok(res))
+    }
+}
+
+fn rejection_problem(
+    status: StatusCode,
+    rejection: &AnyRejection,
+    type_base: Option<&str>,
+) -> Problem {
+    let problem = Problem::new(status).with_detail(rejection.body_text());
+
+    match type_base {
+        Some(base) => problem
+            .with_type(format!("{}{}", base, rejection.name()))
+            .with_title(rejection.title()),
+        None => problem,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::rejection::{JsonRejection, MissingJsonContentType};
+    use serde_json::json;
+
+    #[test]
+    fn serializes_members() {
+        let problem = Problem::new(StatusCode::FORBIDDEN)
+            .with_detail("Your balance is 30")
+            .with_extension("balance", 30);
+        assert_eq!(
+            serde_json::to_value(&problem).unwrap(),
+            json!({
+                "type": "about:blank",
+                "title": "Forbidden",
+                "status": 403,
+                "detail": "Your balance is 30",
+                "balance": 30,
+            })
+        );
+
+        let problem = Problem::new(StatusCode::FORBIDDEN).with_type("https://example.com/credit");
+        assert!(serde_json::to_value(&problem).unwrap().get("title").is_none());
+    }
+
+    #[test]
+    fn renders_rejections() {
+        let res = JsonRejection::from(MissingJsonContentType).into_response();
+        let rejection = res.extensions().get::<AnyRejection>().unwrap();
+
+        let problem = rejection_problem(
+            res.status(),
+            rejection,
+            Some("https://example.com/problems/"),
+        );
+        assert_eq!(
+            serde_json::to_value(&problem).unwrap(),
+            json!({
+                "type": "https://example.com/problems/MissingJsonContentType",
+                "title": "Expected request with `Content-Type: application/json`",
+                "status": 415,
+                "detail": "Expected request with `Content-Type: application/json`",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn layer_renders_rejected_requests() {
+        use crate::{body::Body, extract::Path, routing::post, Json, Router};
+        use tower::ServiceExt;
+
+        async fn problem(uri: &str) -> (StatusCode, Value) {
+            let app = Router::new()
+                .route("/users/:id", post(|_: Path<u32>, _: Json<Value>| async {}))
+                .layer(ProblemDetailsLayer::new().type_base("https://example.com/problems/"));
+            let req = Request::post(uri).body(Body::empty()).unwrap();
+            let res = app.oneshot(req).await.unwrap();
+            assert_eq!(res.headers()[header::CONTENT_TYPE], APPLICATION_PROBLEM_JSON);
+            let status = res.status();
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            (status, serde_json::from_slice(&body).unwrap())
+        }
+
+        let (status, body) = problem("/users/1").await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["type"], "https://example.com/problems/MissingJsonContentType");
+
+        let (status, body) = problem("/users/one").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body,
+            json!({
+                "type": "https://example.com/problems/FailedToDeserializePathParams",
+                "title": "Invalid URL",
+                "status": 400,
+                "detail": "Invalid URL: Cannot parse `\"one\"` to a `u32`",
+            })
+        );
+    }
+}
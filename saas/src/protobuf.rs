@@ -0,0 +1,194 @@
+use crate::extract::Request;
+use crate::extract::{rejection::*, FromRequest};
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use http::{
+    header::{self, HeaderMap, HeaderValue},
+    StatusCode,
+};
+use prost::Message;
+use saas_core::response::{IntoResponse, Response};
+
+/// Protocol Buffers extractor and response.
+///
+/// # As extractor
+///
+/// The request is rejected if it doesn't have a `Content-Type` of `application/x-protobuf` or
+/// `application/protobuf`, or if the body can't be decoded into the target message.
+///
+/// ```rust,no_run
+/// use saas::{routing::post, Protobuf, Router};
+///
+/// #[derive(prost::Message)]
+/// struct CreateUser {
+///     #[prost(string, tag = "1")]
+///     name: String,
+/// }
+///
+/// async fn create_user(Protobuf(payload): Protobuf<CreateUser>) {
+///     // ...
+/// }
+///
+/// let app = Router::new().route("/users", post(create_user));
+/// # let _: Router = app;
+/// ```
+///
+/// # As response
+///
+/// The message is encoded with `Content-Type: application/x-protobuf`.
+#[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+#[derive(Debug, Clone, Copy, Default)]
+#[must_use]
+pub struct Protobuf<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Protobuf<T>
+where
+    T: Message + Default,
+    S: Send + Sync,
+{
+    type Rejection = ProtobufRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if protobuf_content_type(req.headers()) {
+            let bytes = Bytes::from_request(req, state).await?;
+            Self::from_bytes(&bytes)
+        } else {
+            Err(MissingProtobufContentType.into())
+        }
+    }
+}
+
+impl<T> Protobuf<T>
+where
+    T: Message + Default,
+{
+    /// Construct a `Protobuf<T>` from a byte slice.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProtobufRejection> {
+        T::decode(bytes)
+            .map(Protobuf)
+            .map_err(|err| ProtobufDecodeError::from_err(err).into())
+    }
+}
+
+fn protobuf_content_type(headers: &HeaderMap) -> bool {
+    let mime = match headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.parse::<mime::Mime>().ok())
+    {
+        Some(mime) => mime,
+        None => return false,
+    };
+
+    mime.type_() == "application" && matches!(mime.subtype().as_str(), "x-protobuf" | "protobuf")
+}
+
+saas_core::__impl_deref!(Protobuf);
+
+impl<T> From<T> for Protobuf<T> {
+    fn from(inner: T) -> Self {
+        Self(inner)
+    }
+}
+
+impl<T> IntoResponse for Protobuf<T>
+where
+    T: Message,
+{
+    fn into_response(self) -> Response {
+        let mut buf = BytesMut::with_capacity(self.0.encoded_len());
+
+        match self.0.encode(&mut buf) {
+            Ok(()) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/x-protobuf"),
+                )],
+                buf.freeze(),
+            )
+                .into_response(),
+
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(mime::TEXT_PLAIN_UTF_8.as_ref()),
+                )],
+                err.to_string(),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Body, routing::post, Router};
+    use tower::ServiceExt;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct User {
+        #[prost(string, tag = "1")]
+        name: String,
+        #[prost(uint64, tag = "2")]
+        id: u64,
+    }
+
+    async fn create_user(Protobuf(user): Protobuf<User>) -> Protobuf<User> {
+        Protobuf(User { id: 1, ..user })
+    }
+
+    async fn send(content_type: &str, body: Vec<u8>) -> Response {
+        let app = Router::new().route("/users", post(create_user));
+        let req = http::Request::post("/users")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap();
+        app.oneshot(req).await.unwrap()
+    }
+
+    #[test]
+    fn content_type() {
+        let mut headers = HeaderMap::new();
+        for content_type in ["application/x-protobuf", "application/protobuf"] {
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            assert!(protobuf_content_type(&headers));
+        }
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        assert!(!protobuf_content_type(&headers));
+    }
+
+    #[tokio::test]
+    async fn decodes_and_encodes_messages() {
+        let user = User {
+            name: "alice".to_owned(),
+            id: 0,
+        };
+        let res = send("application/x-protobuf", user.encode_to_vec()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/x-protobuf");
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(
+            User::decode(body).unwrap(),
+            User {
+                name: "alice".to_owned(),
+                id: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_messages() {
+        // field 1 claims to be 255 bytes long
+        let res = send("application/protobuf", vec![0x0a, 0xff, 0x01, b'a']).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(body.starts_with(b"Failed to decode the Protobuf body into the target message"));
+
+        let res = send("application/json", Vec::new()).await;
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
@@ -0,0 +1,545 @@
+//! Range requests and `206 Partial Content` responses.
+//!
+//! [`Range`] extracts the byte ranges a client asked for and [`Ranged`] serves them from any
+//! seekable source, so downloads can be resumed:
+//!
+//! ```rust,no_run
+//! use saas::{
+//!     http::StatusCode,
+//!     range::{Range, Ranged},
+//!     routing::get,
+//!     Router,
+//! };
+//!
+//! async fn download(range: Range) -> Result<Ranged<tokio::fs::File>, StatusCode> {
+//!     let file = tokio::fs::File::open("exports/report.csv")
+//!         .await
+//!         .map_err(|_| StatusCode::NOT_FOUND)?;
+//!     let metadata = file.metadata().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+//!
+//!     let mut ranged = Ranged::new(file, metadata.len(), range);
+//!     if let Ok(modified) = metadata.modified() {
+//!         ranged = ranged.last_modified(modified);
+//!     }
+//!     Ok(ranged)
+//! }
+//!
+//! let app = Router::new().route("/exports/report.csv", get(download));
+//! # let _: Router = app;
+//! ```
+
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures_util::stream::{self, Stream};
+use http::{
+    header::{self, HeaderValue},
+    request::Parts,
+    Method, StatusCode,
+};
+use saas_core::{
+    body::Body,
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::hash_map::RandomState,
+    convert::Infallible,
+    hash::{BuildHasher, Hasher},
+    io::{self, SeekFrom},
+    ops,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// requests with more ranges than this are served in full, as allowed by RFC 9110
+const MAX_RANGES: usize = 32;
+
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Extractor for the `Range` and `If-Range` headers.
+///
+/// Only `bytes` ranges of `GET` requests are supported. A missing or malformed `Range` header
+/// results in no ranges, in which case [`Ranged`] serves the whole representation.
+#[derive(Debug, Clone, Default)]
+pub struct Range {
+    ranges: Vec<ByteRange>,
+    if_range: Option<HeaderValue>,
+}
+
+impl Range {
+    /// Parse the value of a `Range` header.
+    ///
+    /// Returns `None` if the header is malformed or doesn't use the `bytes` unit.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (unit, ranges) = value.split_once('=')?;
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+
+        let ranges = ranges
+            .split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+            .map(ByteRange::parse)
+            .collect::<Option<Vec<_>>>()?;
+
+        (!ranges.is_empty() && ranges.len() <= MAX_RANGES).then_some(Self {
+            ranges,
+            if_range: None,
+        })
+    }
+
+    /// The requested ranges, in the order they were requested.
+    pub fn ranges(&self) -> &[ByteRange] {
+        &self.ranges
+    }
+
+    /// Returns `true` if no ranges were requested.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Range
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if parts.method != Method::GET {
+            return Ok(Self::default());
+        }
+
+        let range = parts
+            .headers
+            .get(header::RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Self::parse)
+            .unwrap_or_default();
+
+        Ok(Self {
+            if_range: parts.headers.get(header::IF_RANGE).cloned(),
+            ..range
+        })
+    }
+}
+
+/// A single range of a `Range` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `first-last`, both inclusive.
+    Bounded(u64, u64),
+    /// `first-`, from `first` to the end.
+    From(u64),
+    /// `-length`, the last `length` bytes.
+    Suffix(u64),
+}
+
+impl ByteRange {
+    fn parse(range: &str) -> Option<Self> {
+        let (first, last) = range.split_once('-')?;
+        let (first, last) = (first.trim(), last.trim());
+
+        match (first.is_empty(), last.is_empty()) {
+            (false, false) => {
+                let (first, last) = (first.parse().ok()?, last.parse().ok()?);
+                (first <= last).then_some(Self::Bounded(first, last))
+            }
+            (false, true) => first.parse().ok().map(Self::From),
+            (true, false) => last.parse().ok().map(Self::Suffix),
+            (true, true) => None,
+        }
+    }
+
+    /// Resolve the range against a representation of `len` bytes.
+    ///
+    /// Returns `None` if the range is unsatisfiable.
+    pub fn resolve(&self, len: u64) -> Option<ops::Range<u64>> {
+        match *self {
+            Self::Bounded(first, last) if first < len => Some(first..last.min(len - 1) + 1),
+            Self::From(first) if first < len => Some(first..len),
+            Self::Suffix(length) if length > 0 && len > 0 => Some(len.saturating_sub(length)..len),
+            _ => None,
+        }
+    }
+}
+
+/// Response that serves the ranges requested with [`Range`] from a seekable source.
+///
+/// Responds with:
+///
+/// - `206 Partial Content` and `Content-Range` for a single satisfiable range.
+/// - `206 Partial Content` with a `multipart/byteranges` body for several satisfiable ranges.
+/// - `416 Range Not Satisfiable` if none of the ranges are satisfiable.
+/// - `200 OK` with the whole representation if no ranges were requested, or if `If-Range`
+///   doesn't match the [`etag`](Ranged::etag) or [`last_modified`](Ranged::last_modified)
+///   given.
+/// - `200 OK` with the whole representation if the ranges aren't in ascending order, or if
+///   more than two of them overlap, as allowed by [RFC 9110].
+///
+/// Overlapping and adjacent ranges are merged, so `bytes=0-9,10-19` is served as `0-19`.
+///
+/// All responses include `Accept-Ranges: bytes`.
+///
+/// [RFC 9110]: https://www.rfc-editor.org/rfc/rfc9110#section-14.2
+#[derive(Debug)]
+#[must_use]
+pub struct Ranged<R> {
+    source: R,
+    len: u64,
+    range: Range,
+    content_type: Option<HeaderValue>,
+    etag: Option<HeaderValue>,
+    last_modified: Option<SystemTime>,
+}
+
+impl<R> Ranged<R> {
+    /// Create a new `Ranged` serving `range` from `source`, which is `len` bytes long.
+    pub fn new(source: R, len: u64, range: Range) -> Self {
+        Self {
+            source,
+            len,
+            range,
+            content_type: None,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// Set the `Content-Type` of the representation.
+    ///
+    /// Defaults to `application/octet-stream`.
+    pub fn content_type(mut self, content_type: HeaderValue) -> Self {
+        self.content_type = Some(content_type);
+        self
+    }
+
+    /// Set the entity tag of the representation, such as `"v2"`.
+    ///
+    /// Sent as `ETag` and used to evaluate `If-Range`.
+    pub fn etag(mut self, etag: HeaderValue) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+
+    /// Set the modification time of the representation.
+    ///
+    /// Sent as `Last-Modified` and used to evaluate `If-Range`.
+    pub fn last_modified(mut self, last_modified: SystemTime) -> Self {
+        self.last_modified = Some(last_modified);
+        self
+    }
+
+    fn if_range_matches(&self) -> bool {
+        let if_range = match &self.range.if_range {
+            Some(if_range) => if_range.as_bytes(),
+            None => return true,
+        };
+
+        if if_range.starts_with(b"\"") {
+            // entity tags must match strongly
+            self.etag.as_ref().is_some_and(|etag| etag.as_bytes() == if_range)
+        } else if if_range.starts_with(b"W/") {
+            false
+        } else {
+            let date = std::str::from_utf8(if_range)
+                .ok()
+                .and_then(|date| httpdate::parse_http_date(date).ok());
+            match (date, self.last_modified) {
+                (Some(date), Some(last_modified)) => unix_secs(date) == unix_secs(last_modified),
+                _ => false,
+            }
+        }
+    }
+}
+
+fn unix_secs(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|time| time.as_secs())
+}
+
+impl<R> IntoResponse for Ranged<R>
+where
+    R: AsyncRead + AsyncSeek + Send + Unpin + 'static,
+{
+    fn into_response(self) -> Response {
+        let requested = if self.if_range_matches() {
+            self.range.ranges.as_slice()
+        } else {
+            &[]
+        };
+
+        let content_type = self
+            .content_type
+            .clone()
+            .unwrap_or_else(|| HeaderValue::from_static("application/octet-stream"));
+        let len = self.len;
+
+        let ranges = if requested.is_empty() {
+            None
+        } else {
+            coalesce(requested.iter().filter_map(|range| range.resolve(len)))
+        };
+
+        let mut res = match ranges.as_deref() {
+            None => {
+                let mut res = Response::new(body(self.source, vec![Segment::Range(0..len)]));
+                res.headers_mut().insert(header::CONTENT_TYPE, content_type);
+                res.headers_mut().insert(header::CONTENT_LENGTH, len.into());
+                res
+            }
+            Some([]) => {
+                let mut res = StatusCode::RANGE_NOT_SATISFIABLE.into_response();
+                res.headers_mut()
+                    .insert(header::CONTENT_RANGE, content_range(None, len));
+                res
+            }
+            Some([range]) => {
+                let content_length = range.end - range.start;
+                let content_range = content_range(Some(range), len);
+
+                let mut res =
+                    Response::new(body(self.source, vec![Segment::Range(range.clone())]));
+                *res.status_mut() = StatusCode::PARTIAL_CONTENT;
+                let headers = res.headers_mut();
+                headers.insert(header::CONTENT_TYPE, content_type);
+                headers.insert(header::CONTENT_RANGE, content_range);
+                headers.insert(header::CONTENT_LENGTH, content_length.into());
+                res
+            }
+            Some(ranges) => {
+                let boundary = boundary();
+                let mut segments = Vec::with_capacity(ranges.len() * 2 + 1);
+                for (idx, range) in ranges.iter().enumerate() {
+                    let mut part = String::new();
+                    if idx > 0 {
+                        part.push_str("\r\n");
+                    }
+                    part.push_str(&format!(
+                        "--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+                        boundary,
+                        content_type.to_str().unwrap_or("application/octet-stream"),
+                        content_range(Some(range), len).to_str().unwrap_or_default(),
+                    ));
+                    segments.push(Segment::Bytes(part.into()));
+                    segments.push(Segment::Range(range.clone()));
+                }
+                segments.push(Segment::Bytes(format!("\r\n--{}--\r\n", boundary).into()));
+
+                let content_length = segments
+                    .iter()
+                    .map(|segment| match segment {
+                        Segment::Bytes(bytes) => bytes.len() as u64,
+                        Segment::Range(range) => range.end - range.start,
+                    })
+                    .sum::<u64>();
+
+                let mut res = Response::new(body(self.source, segments));
+                *res.status_mut() = StatusCode::PARTIAL_CONTENT;
+                let headers = res.headers_mut();
+                headers.insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::try_from(format!("multipart/byteranges; boundary={}", boundary))
+                        .expect("boundary is a valid header value"),
+                );
+                headers.insert(header::CONTENT_LENGTH, content_length.into());
+                res
+            }
+        };
+
+        let headers = res.headers_mut();
+        headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        if let Some(etag) = self.etag {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Some(last_modified) = self.last_modified {
+            let last_modified = HeaderValue::try_from(httpdate::fmt_http_date(last_modified))
+                .expect("HTTP dates are valid header values");
+            headers.insert(header::LAST_MODIFIED, last_modified);
+        }
+        res
+    }
+}
+
+// Merges overlapping and adjacent ranges.
+//
+// Returns `None` if the ranges aren't in ascending order or more than one of them overlaps the
+// ones before it, as RFC 9110 allows serving such requests in full. They are rarely sent by
+// well-behaved clients and would make the response larger than the representation.
+fn coalesce<I>(ranges: I) -> Option<Vec<ops::Range<u64>>>
+where
+    I: IntoIterator<Item = ops::Range<u64>>,
+{
+    let mut merged: Vec<ops::Range<u64>> = Vec::new();
+    let mut overlapping = 0;
+
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start < last.start => return None,
+            Some(last) if range.start <= last.end => {
+                if range.start < last.end {
+                    overlapping += 1;
+                    if overlapping > 1 {
+                        return None;
+                    }
+                }
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+
+    Some(merged)
+}
+
+fn content_range(range: Option<&ops::Range<u64>>, len: u64) -> HeaderValue {
+    let value = match range {
+        Some(range) => format!("bytes {}-{}/{}", range.start, range.end - 1, len),
+        None => format!("bytes */{}", len),
+    };
+    HeaderValue::try_from(value).expect("content range is a valid header value")
+}
+
+fn boundary() -> String {
+    // `RandomState` is randomly seeded, which is good enough to not collide with the content
+    let random = || RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", random(), random())
+}
+
+enum Segment {
+    Bytes(Bytes),
+    Range(ops::Range<u64>),
+}
+
+fn body<R>(source: R, segments: Vec<Segment>) -> Body
+where
+    R: AsyncRead + AsyncSeek + Send + Unpin + 'static,
+{
+    Body::from_stream(read_segments(source, segments))
+}
+
+fn read_segments<R>(source: R, segments: Vec<Segment>) -> impl Stream<Item = io::Result<Bytes>>
+where
+    R: AsyncRead + AsyncSeek + Send + Unpin + 'static,
+{
+    stream::try_unfold(
+        (source, segments.into_iter(), 0),
+        |(mut source, mut segments, mut remaining): (R, _, u64)| async move {
+            loop {
+                if remaining > 0 {
+                    let mut buf = BytesMut::with_capacity(remaining.min(CHUNK_SIZE) as usize);
+                    let read = (&mut source)
+                        .take(remaining.min(CHUNK_SIZE))
+                        .read_buf(&mut buf)
+                        .await?;
+                    if read == 0 {
+                        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                    }
+                    remaining -= read as u64;
+                    return Ok(Some((buf.freeze(), (source, segments, remaining))));
+                }
+
+                match segments.next() {
+                    Some(Segment::Bytes(bytes)) => {
+                        return Ok(Some((bytes, (source, segments, remaining))))
+                    }
+                    Some(Segment::Range(range)) => {
+                        source.seek(SeekFrom::Start(range.start)).await?;
+                        remaining = range.end - range.start;
+                    }
+                    None => return Ok(None),
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn range(value: &str) -> Range {
+        Range::parse(value).unwrap_or_default()
+    }
+
+    #[test]
+    fn parses_and_resolves_ranges() {
+        assert_eq!(
+            range("bytes=0-499, 500-, -200").ranges(),
+            [
+                ByteRange::Bounded(0, 499),
+                ByteRange::From(500),
+                ByteRange::Suffix(200)
+            ]
+        );
+        assert!(range("bytes=5-1").is_empty());
+        assert!(range("items=0-1").is_empty());
+        assert!(range("bytes=-").is_empty());
+
+        assert_eq!(ByteRange::Bounded(0, 499).resolve(100), Some(0..100));
+        assert_eq!(ByteRange::From(100).resolve(100), None);
+        assert_eq!(ByteRange::Suffix(200).resolve(100), Some(0..100));
+        assert_eq!(ByteRange::Suffix(0).resolve(100), None);
+    }
+
+    async fn respond(range: Range) -> (Response, String) {
+        let res = Ranged::new(Cursor::new(b"0123456789".to_vec()), 10, range)
+            .etag(HeaderValue::from_static("\"v1\""))
+            .into_response();
+        let (parts, body) = res.into_parts();
+        let body = hyper::body::to_bytes(body).await.unwrap();
+        (
+            Response::from_parts(parts, Body::empty()),
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn serves_ranges() {
+        let (res, body) = respond(Range::default()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::ACCEPT_RANGES], "bytes");
+        assert_eq!(body, "0123456789");
+
+        let (res, body) = respond(range("bytes=2-4")).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.headers()[header::CONTENT_RANGE], "bytes 2-4/10");
+        assert_eq!(body, "234");
+
+        let (res, body) = respond(range("bytes=0-1,-2")).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers()[header::CONTENT_LENGTH],
+            body.len().to_string().as_str()
+        );
+        assert!(body.contains("Content-Range: bytes 0-1/10\r\n\r\n01\r\n"));
+        assert!(body.contains("Content-Range: bytes 8-9/10\r\n\r\n89\r\n"));
+
+        let (res, body) = respond(range("bytes=0-3,2-5,6-7")).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.headers()[header::CONTENT_RANGE], "bytes 0-7/10");
+        assert_eq!(body, "01234567");
+
+        let (res, body) = respond(range("bytes=0-,0-,0-")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(body, "0123456789");
+
+        let (res, body) = respond(range("bytes=6-7,0-1")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(body, "0123456789");
+
+        let (res, _) = respond(range("bytes=20-")).await;
+        assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(res.headers()[header::CONTENT_RANGE], "bytes */10");
+
+        let stale = Range {
+            if_range: Some(HeaderValue::from_static("\"v0\"")),
+            ..range("bytes=2-4")
+        };
+        let (res, body) = respond(stale).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(body, "0123456789");
+    }
+}
@@ -11,6 +11,22 @@ pub mod sse;
 #[cfg(feature = "json")]
 pub use crate::Json;
 
+#[doc(no_inline)]
+#[cfg(feature = "msgpack")]
+pub use crate::MsgPack;
+
+#[doc(no_inline)]
+#[cfg(feature = "cbor")]
+pub use crate::Cbor;
+
+#[doc(no_inline)]
+#[cfg(feature = "protobuf")]
+pub use crate::Protobuf;
+
+#[doc(no_inline)]
+#[cfg(feature = "xml")]
+pub use crate::Xml;
+
 #[cfg(feature = "form")]
 #[doc(no_inline)]
 pub use crate::form::Form;
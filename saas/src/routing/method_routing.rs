@@ -10,7 +10,11 @@ use crate::{
     response::Response,
     routing::{future::RouteFuture, Fallback, MethodFilter, Route},
 };
-use saas_core::{extract::Request, response::IntoResponse, BoxError};
+use saas_core::{
+    extract::{DefaultBodyLimit, Request},
+    response::IntoResponse,
+    BoxError,
+};
 use bytes::BytesMut;
 use std::{
     convert::Infallible,
@@ -347,6 +351,7 @@ pub struct MethodRouter<S = (), E = Infallible> {
     trace: MethodEndpoint<S, E>,
     fallback: Fallback<S, E>,
     allow_header: AllowHeader,
+    body_limit: Option<DefaultBodyLimit>,
 }
 
 #[derive(Debug, Clone)]
@@ -385,6 +390,7 @@ impl<S, E> fmt::Debug for MethodRouter<S, E> {
             .field("trace", &self.trace)
             .field("fallback", &self.fallback)
             .field("allow_header", &self.allow_header)
+            .field("body_limit", &self.body_limit)
             .finish()
     }
 }
@@ -457,6 +463,7 @@ where
             trace: MethodEndpoint::None,
             allow_header: AllowHeader::None,
             fallback: Fallback::Default(fallback),
+            body_limit: None,
         }
     }
 
@@ -472,6 +479,7 @@ where
             trace: self.trace.with_state(&state),
             allow_header: self.allow_header,
             fallback: self.fallback.with_state(state),
+            body_limit: self.body_limit,
         }
     }
 
@@ -642,6 +650,7 @@ where
             trace: self.trace.map(layer_fn.clone()),
             fallback: self.fallback.map(layer_fn),
             allow_header: self.allow_header,
+            body_limit: self.body_limit,
         }
     }
 
@@ -732,6 +741,9 @@ where
             .expect("Cannot merge two `MethodRouter`s that both have a fallback");
 
         self.allow_header = self.allow_header.merge(other.allow_header);
+
+        // the limit of the router merged in last wins
+        self.body_limit = other.body_limit.or(self.body_limit);
         self
     }
 
@@ -754,12 +766,43 @@ where
         self.layer(HandleErrorLayer::new(f))
     }
 
+    /// Set the [`DefaultBodyLimit`] for the requests to this route.
+    ///
+    /// Takes precedence over `DefaultBodyLimit` layers, regardless of the order they are
+    /// added in, and can be inspected with [`Router::body_limits`]. When two `MethodRouter`s
+    /// that both set a limit are merged, the limit of the one merged in last is used.
+    ///
+    /// ```rust,no_run
+    /// use saas::{body::Bytes, extract::DefaultBodyLimit, routing::post, Router};
+    ///
+    /// async fn upload(body: Bytes) {}
+    ///
+    /// let app = Router::new()
+    ///     .route(
+    ///         "/upload",
+    ///         post(upload).default_body_limit(DefaultBodyLimit::max(500 * 1024 * 1024)),
+    ///     )
+    ///     .layer(DefaultBodyLimit::max(1024 * 1024));
+    /// # let _: Router = app;
+    /// ```
+    ///
+    /// [`Router::body_limits`]: crate::Router::body_limits
+    pub fn default_body_limit(mut self, limit: DefaultBodyLimit) -> Self {
+        self.body_limit = Some(limit);
+        self
+    }
+
+    /// The body limit set with [`MethodRouter::default_body_limit`], if any.
+    pub fn body_limit(&self) -> Option<&DefaultBodyLimit> {
+        self.body_limit.as_ref()
+    }
+
     fn skip_allow_header(mut self) -> Self {
         self.allow_header = AllowHeader::Skip;
         self
     }
 
-    pub(crate) fn call_with_state(&mut self, req: Request, state: S) -> RouteFuture<E> {
+    pub(crate) fn call_with_state(&mut self, mut req: Request, state: S) -> RouteFuture<E> {
         macro_rules! call {
             (
                 $req:expr,
@@ -797,8 +840,13 @@ where
             trace,
             fallback,
             allow_header,
+            body_limit,
         } = self;
 
+        if let Some(limit) = body_limit {
+            limit.clone().__insert_route_limit_into(req.extensions_mut());
+        }
+
         call!(req, method, HEAD, head);
         call!(req, method, HEAD, get);
         call!(req, method, GET, get);
@@ -852,6 +900,7 @@ impl<S, E> Clone for MethodRouter<S, E> {
             trace: self.trace.clone(),
             fallback: self.fallback.clone(),
             allow_header: self.allow_header.clone(),
+            body_limit: self.body_limit.clone(),
         }
     }
 }
@@ -4,12 +4,14 @@ use crate::extract::connect_info::IntoMakeServiceWithConnectInfo;
 use crate::{
     body::{Body, HttpBody},
     boxed::{BoxedIntoRoute},
+    extract::rejection::AnyRejection,
     handler::Handler,
+    middleware::map_rejection,
     util::try_downcast,
 };
 
 use saas_core::{
-    extract::Request,
+    extract::{DefaultBodyLimit, Request},
     response::{IntoResponse, Response},
 };
 use std::{
@@ -242,6 +244,55 @@ where
         }
     }
 
+    /// The body limits set on routes with [`MethodRouter::default_body_limit`], sorted by
+    /// path.
+    ///
+    /// Limits set with `DefaultBodyLimit` layers aren't included.
+    pub fn body_limits(&self) -> Vec<(&str, &DefaultBodyLimit)> {
+        self.path_router.body_limits()
+    }
+
+    /// Replace the response of every rejection from the built-in extractors with the
+    /// response returned by `f`.
+    ///
+    /// `f` receives an [`AnyRejection`] with the status, body text and source error of the
+    /// rejection, and can downcast it to the rejection type of the extractor:
+    ///
+    /// ```rust,no_run
+    /// use saas::{
+    ///     extract::rejection::{AnyRejection, JsonRejection},
+    ///     routing::post,
+    ///     Json, Router,
+    /// };
+    /// use serde_json::{json, Value};
+    ///
+    /// async fn create_user(Json(payload): Json<Value>) {}
+    ///
+    /// let app = Router::new()
+    ///     .route("/users", post(create_user))
+    ///     .map_rejection(|rejection: AnyRejection| {
+    ///         let code = match rejection.downcast_ref::<JsonRejection>() {
+    ///             Some(JsonRejection::JsonSyntaxError(_)) => "invalid_json",
+    ///             Some(_) => "invalid_body",
+    ///             None => "bad_request",
+    ///         };
+    ///         let body = json!({ "code": code, "message": rejection.body_text() });
+    ///         (rejection.status(), Json(body))
+    ///     });
+    /// # let _: Router = app;
+    /// ```
+    ///
+    /// Like [`Router::layer`], this only applies to routes added before it is called.
+    ///
+    /// [`AnyRejection`]: crate::extract::rejection::AnyRejection
+    pub fn map_rejection<F, R>(self, f: F) -> Self
+    where
+        F: Fn(AnyRejection) -> R + Clone + Send + Sync + 'static,
+        R: IntoResponse + 'static,
+    {
+        self.layer(map_rejection(f))
+    }
+
     pub fn fallback<H, T>(mut self, handler: H) -> Self
     where
         H: Handler<T, S>,
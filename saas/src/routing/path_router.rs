@@ -3,7 +3,7 @@ use crate::{
         response::{IntoResponse}
     };
 use matchit::MatchError;
-use saas_core::extract::Request;
+use saas_core::extract::{DefaultBodyLimit, Request};
 use serde::de::IntoDeserializer;
 use tower_layer::Layer;
 use tower_service::Service;
@@ -304,7 +304,7 @@ where
 
             if req.extensions().get::<OriginalUri>().is_none() {
                 let original_uri = OriginalUri(req.uri().clone());
-                req.extensions_mut().insert(original_uri);
+                crate::extension::insert_cloneable(req.extensions_mut(), original_uri);
             }
         }
 
@@ -349,6 +349,22 @@ where
         }
     }
 
+    pub(super) fn body_limits(&self) -> Vec<(&str, &DefaultBodyLimit)> {
+        let mut limits: Vec<_> = self
+            .routes
+            .iter()
+            .filter_map(|(id, endpoint)| match endpoint {
+                Endpoint::MethodRouter(method_router) => {
+                    let path = self.node.route_id_to_path.get(id)?;
+                    Some((&**path, method_router.body_limit()?))
+                }
+                Endpoint::Route(_) => None,
+            })
+            .collect();
+        limits.sort_by_key(|(path, _)| *path);
+        limits
+    }
+
     pub(super) fn replace_endpoint(&mut self, path: &str, endpoint: Endpoint<S>) {
         match self.node.at(path) {
             Ok(match_) => {
@@ -0,0 +1,51 @@
+use crate::{
+    body::Bytes,
+    extract::DefaultBodyLimit,
+    routing::{get, post, MethodRouter},
+    Router,
+};
+use http::{Request, StatusCode};
+use saas_core::body::Body;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn route_body_limit() {
+    let app = Router::new()
+        .route(
+            "/upload",
+            post(|_: Bytes| async {}).default_body_limit(DefaultBodyLimit::max(32)),
+        )
+        .route("/other", post(|_: Bytes| async {}))
+        .layer(DefaultBodyLimit::max(8));
+
+    let limits = app.body_limits();
+    assert_eq!(limits.len(), 1);
+    assert_eq!(limits[0].0, "/upload");
+    assert_eq!(limits[0].1.limit(), Some(32));
+
+    let req = Request::post("/upload").body(Body::from(vec![0; 16])).unwrap();
+    let res = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let req = Request::post("/other").body(Body::from(vec![0; 16])).unwrap();
+    let res = app.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(
+        body,
+        "Request body is too large: length limit of 8 bytes exceeded"
+    );
+}
+
+#[test]
+fn merged_route_body_limit_uses_the_last_limit() {
+    let method_router: MethodRouter = post(|_: Bytes| async {})
+        .default_body_limit(DefaultBodyLimit::max(8))
+        .merge(get(|| async {}).default_body_limit(DefaultBodyLimit::max(32)));
+    assert_eq!(method_router.body_limit().unwrap().limit(), Some(32));
+
+    let method_router: MethodRouter = post(|_: Bytes| async {})
+        .default_body_limit(DefaultBodyLimit::max(8))
+        .merge(get(|| async {}));
+    assert_eq!(method_router.body_limit().unwrap().limit(), Some(8));
+}
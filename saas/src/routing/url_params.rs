@@ -3,8 +3,9 @@ use std::sync::Arc;
 use http::Extensions;
 use matchit::Params;
 
-use crate::util::PercentDecodedStr;
+use crate::{extension::insert_cloneable, util::PercentDecodedStr};
 
+#[derive(Clone)]
 pub(crate) enum UrlParams {
     Params(Vec<(Arc<str>, PercentDecodedStr)>),
     InvalidUtf8InPathParams { key: Arc<str>},
@@ -35,13 +36,13 @@ pub(crate) fn insert_url_params(extensions: &mut Extensions, params: Params) {
             unreachable!("we check for this state earlier in this method")
         }
         (_, Err(invalid_key)) => {
-            extensions.insert(UrlParams::InvalidUtf8InPathParams { key: invalid_key });
+            insert_cloneable(extensions, UrlParams::InvalidUtf8InPathParams { key: invalid_key });
         }
         (Some(UrlParams::Params(current)), Ok(params)) => {
             current.extend(params);
         }
         (None,Ok(params)) => {
-            extensions.insert(params);
+            insert_cloneable(extensions, UrlParams::Params(params));
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,238 @@
+use crate::extract::{rejection::AnyRejection, FromRequestParts};
+use async_trait::async_trait;
+use headers::HeaderMapExt;
+use http::request::Parts;
+use saas_core::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
+use std::{convert::Infallible, ops::Deref};
+
+/// Extractor and response that works with typed header values from [`headers`].
+///
+/// # As extractor
+///
+/// In general, it's recommended to extract only the needed headers via `TypedHeader` rather than
+/// removing all headers with the `HeaderMap` extractor.
+///
+/// ```rust,no_run
+/// use saas::{
+///     TypedHeader,
+///     headers::UserAgent,
+///     routing::get,
+///     Router,
+/// };
+///
+/// async fn users_teams_show(
+///     TypedHeader(user_agent): TypedHeader<UserAgent>,
+/// ) {
+///     // ...
+/// }
+///
+/// let app = Router::new().route("/users/:user_id/team/:team_id", get(users_teams_show));
+/// # let _: Router = app;
+/// ```
+///
+/// # As response
+///
+/// ```rust
+/// use saas::{
+///     TypedHeader,
+///     response::IntoResponse,
+///     headers::ContentType,
+/// };
+///
+/// async fn handler() -> (TypedHeader<ContentType>, &'static str) {
+///     (
+///         TypedHeader(ContentType::text_utf8()),
+///         "Hello, World!",
+///     )
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "headers")))]
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub struct TypedHeader<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for TypedHeader<T>
+where
+    T: headers::Header,
+    S: Send + Sync,
+{
+    type Rejection = TypedHeaderRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let mut values = parts.headers.get_all(T::name()).iter();
+        let is_missing = values.size_hint() == (0, Some(0));
+        T::decode(&mut values)
+            .map(Self)
+            .map_err(|err| TypedHeaderRejection {
+                name: T::name(),
+                reason: if is_missing {
+                    // Report a more precise rejection for the missing header case.
+                    TypedHeaderRejectionReason::Missing
+                } else {
+                    TypedHeaderRejectionReason::Error(err)
+                },
+            })
+    }
+}
+
+impl<T> Deref for TypedHeader<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> IntoResponseParts for TypedHeader<T>
+where
+    T: headers::Header,
+{
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        res.headers_mut().typed_insert(self.0);
+        Ok(res)
+    }
+}
+
+impl<T> IntoResponse for TypedHeader<T>
+where
+    T: headers::Header,
+{
+    fn into_response(self) -> Response {
+        let mut res = ().into_response();
+        res.headers_mut().typed_insert(self.0);
+        res
+    }
+}
+
+/// Rejection used for [`TypedHeader`](super::TypedHeader).
+#[cfg_attr(docsrs, doc(cfg(feature = "headers")))]
+#[derive(Debug)]
+pub struct TypedHeaderRejection {
+    name: &'static http::header::HeaderName,
+    reason: TypedHeaderRejectionReason,
+}
+
+impl TypedHeaderRejection {
+    /// Name of the header that caused the rejection
+    pub fn name(&self) -> &http::header::HeaderName {
+        self.name
+    }
+
+    /// Reason why the header extraction has failed
+    pub fn reason(&self) -> &TypedHeaderRejectionReason {
+        &self.reason
+    }
+
+    /// Get the response body text used for this rejection.
+    pub fn body_text(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get the status code used for this rejection.
+    pub fn status(&self) -> http::StatusCode {
+        http::StatusCode::BAD_REQUEST
+    }
+}
+
+/// Additional information regarding a [`TypedHeaderRejection`]
+#[cfg_attr(docsrs, doc(cfg(feature = "headers")))]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TypedHeaderRejectionReason {
+    /// The header was missing from the HTTP request
+    Missing,
+    /// An error occurred when parsing the header from the HTTP request
+    Error(headers::Error),
+}
+
+impl IntoResponse for TypedHeaderRejection {
+    fn into_response(self) -> Response {
+        saas_core::__log_rejection!(
+            rejection_type = TypedHeaderRejection,
+            body_text = self.body_text(),
+            status = self.status(),
+        );
+        let status = self.status();
+        let body_text = self.body_text();
+        let (name, title, detail) = match &self.reason {
+            TypedHeaderRejectionReason::Missing => {
+                ("MissingTypedHeader", "Missing request header", self.name.to_string())
+            }
+            TypedHeaderRejectionReason::Error(_) => {
+                ("InvalidTypedHeader", "Invalid request header", self.name.to_string())
+            }
+        };
+        let mut res = (status, body_text.clone()).into_response();
+        res.extensions_mut().insert(
+            AnyRejection::__new(status, body_text, self).__with_message(name, title, Some(detail)),
+        );
+        res
+    }
+}
+
+impl std::fmt::Display for TypedHeaderRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.reason {
+            TypedHeaderRejectionReason::Missing => {
+                write!(f, "Header of type `{}` was missing", self.name)
+            }
+            TypedHeaderRejectionReason::Error(err) => {
+                write!(f, "{} ({})", err, self.name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TypedHeaderRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.reason {
+            TypedHeaderRejectionReason::Error(err) => Some(err),
+            TypedHeaderRejectionReason::Missing => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Request;
+
+    #[tokio::test]
+    async fn missing_and_malformed_are_distinct() {
+        let (mut parts, _) = Request::new(()).into_parts();
+        let rejection = TypedHeader::<headers::ContentLength>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            rejection.reason(),
+            TypedHeaderRejectionReason::Missing
+        ));
+
+        let (mut parts, _) = Request::builder()
+            .header("content-length", "not a number")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let rejection = TypedHeader::<headers::ContentLength>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            rejection.reason(),
+            TypedHeaderRejectionReason::Error(_)
+        ));
+    }
+
+    #[test]
+    fn into_response_parts_sets_header() {
+        let res = (
+            TypedHeader(headers::ContentType::json()),
+            "{}",
+        )
+            .into_response();
+        assert_eq!(res.headers()["content-type"], "application/json");
+    }
+}
@@ -0,0 +1,177 @@
+use crate::extract::Request;
+use crate::extract::{rejection::*, FromRequest};
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{
+    header::{self, HeaderMap, HeaderValue},
+    StatusCode,
+};
+use saas_core::response::{IntoResponse, Response};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Serialize,
+};
+
+/// XML extractor and response.
+///
+/// Works the same way as [`Json`](crate::Json) but for XML bodies, using [`quick_xml`]'s serde
+/// support.
+///
+/// # As extractor
+///
+/// The request is rejected if it doesn't have a `Content-Type` of `application/xml`,
+/// `text/xml` or `application/*+xml`, if the body isn't well formed XML, or if it can't be
+/// deserialized into the target type. In the last case the error includes the path of the
+/// element that failed, for example `item[0].title`.
+///
+/// ```rust,no_run
+/// use saas::{routing::post, Router, Xml};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Notification {
+///     event: String,
+///     id: u64,
+/// }
+///
+/// async fn webhook(Xml(notification): Xml<Notification>) {
+///     // ...
+/// }
+///
+/// let app = Router::new().route("/webhook", post(webhook));
+/// # let _: Router = app;
+/// ```
+///
+/// # As response
+///
+/// The value is serialized with `Content-Type: application/xml`. The root element is named
+/// after the type.
+///
+/// [`quick_xml`]: https://docs.rs/quick-xml
+#[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+#[derive(Debug, Clone, Copy, Default)]
+#[must_use]
+pub struct Xml<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Xml<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = XmlRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if xml_content_type(req.headers()) {
+            let bytes = Bytes::from_request(req, state).await?;
+            Self::from_bytes(&bytes)
+        } else {
+            Err(MissingXmlContentType.into())
+        }
+    }
+}
+
+impl<T> Xml<T>
+where
+    T: DeserializeOwned,
+{
+    /// Construct a `Xml<T>` from a byte slice.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, XmlRejection> {
+        let text = std::str::from_utf8(bytes).map_err(XmlSyntaxError::from_err)?;
+        let deserializer = &mut quick_xml::de::Deserializer::from_str(text);
+
+        match serde_path_to_error::deserialize(deserializer) {
+            Ok(value) => Ok(Xml(value)),
+            Err(err) => {
+                // quick-xml doesn't tell structural errors apart from mismatched types, so
+                // check whether the document itself is well formed
+                if quick_xml::de::from_str::<IgnoredAny>(text).is_ok() {
+                    Err(XmlDataError::from_err(err).into())
+                } else {
+                    Err(XmlSyntaxError::from_err(err).into())
+                }
+            }
+        }
+    }
+}
+
+fn xml_content_type(headers: &HeaderMap) -> bool {
+    let mime = match headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.parse::<mime::Mime>().ok())
+    {
+        Some(mime) => mime,
+        None => return false,
+    };
+
+    (mime.type_() == "application" || mime.type_() == "text")
+        && (mime.subtype() == "xml" || mime.suffix().is_some_and(|name| name == "xml"))
+}
+
+saas_core::__impl_deref!(Xml);
+
+impl<T> From<T> for Xml<T> {
+    fn from(inner: T) -> Self {
+        Self(inner)
+    }
+}
+
+impl<T> IntoResponse for Xml<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        match quick_xml::se::to_string(&self.0) {
+            Ok(xml) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/xml"),
+                )],
+                xml,
+            )
+                .into_response(),
+
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(mime::TEXT_PLAIN_UTF_8.as_ref()),
+                )],
+                err.to_string(),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Feed {
+        item: Vec<Item>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Item {
+        id: u32,
+    }
+
+    #[test]
+    fn data_errors_include_path() {
+        let feed = Xml::<Feed>::from_bytes(b"<feed><item><id>1</id></item></feed>").unwrap();
+        assert_eq!(feed.0.item, [Item { id: 1 }]);
+
+        let rejection =
+            Xml::<Feed>::from_bytes(b"<feed><item><id>1</id></item><item><id>x</id></item></feed>")
+                .unwrap_err();
+        assert!(matches!(rejection, XmlRejection::XmlDataError(_)));
+        assert!(rejection.body_text().contains("item[1].id"));
+
+        let rejection = Xml::<Feed>::from_bytes(b"<feed><item>").unwrap_err();
+        assert!(matches!(rejection, XmlRejection::XmlSyntaxError(_)));
+    }
+}